@@ -0,0 +1,96 @@
+// *********************************************************************************************************************
+// Complex number arithmetic used by the escape time calculations
+// *********************************************************************************************************************
+
+use serde_derive::Deserialize;
+
+// From JavaScript a complex number is written as {"re": 0.0, "im": 0.0}
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub struct Complex {
+  pub re: f64,
+  pub im: f64,
+}
+
+impl Complex {
+  pub fn new(re: f64, im: f64) -> Complex {
+    Complex { re, im }
+  }
+
+  pub fn add(self, other: Complex) -> Complex {
+    Complex {
+      re: self.re + other.re,
+      im: self.im + other.im,
+    }
+  }
+
+  pub fn sub(self, other: Complex) -> Complex {
+    Complex {
+      re: self.re - other.re,
+      im: self.im - other.im,
+    }
+  }
+
+  pub fn mul(self, other: Complex) -> Complex {
+    Complex {
+      re: self.re * other.re - self.im * other.im,
+      im: self.re * other.im + self.im * other.re,
+    }
+  }
+
+  // Squaring is cheaper than general multiplication: (a + ib)^2 = a^2 - b^2 + 2iab
+  pub fn sqr(self) -> Complex {
+    Complex {
+      re: self.re * self.re - self.im * self.im,
+      im: 2.0 * self.re * self.im,
+    }
+  }
+
+  // The square of the modulus |z|^2, which avoids the cost of a square root
+  pub fn abs_sq(self) -> f64 {
+    self.re * self.re + self.im * self.im
+  }
+
+  // (a + ib) / (c + id) = ((ac + bd) + i(bc - ad)) / (c^2 + d^2)
+  pub fn div(self, other: Complex) -> Complex {
+    let denominator = other.abs_sq();
+
+    Complex {
+      re: (self.re * other.re + self.im * other.im) / denominator,
+      im: (self.im * other.re - self.re * other.im) / denominator,
+    }
+  }
+
+  // Raise to an integer power by repeated squaring, which needs O(log n) rather than O(n) multiplications
+  pub fn powi(self, n: u32) -> Complex {
+    let mut result = Complex::new(1.0, 0.0);
+    let mut base = self;
+    let mut n = n;
+
+    while n > 0 {
+      if n & 1 == 1 {
+        result = result.mul(base);
+      }
+
+      base = base.sqr();
+      n >>= 1;
+    }
+
+    result
+  }
+
+  pub fn conj(self) -> Complex {
+    Complex {
+      re: self.re,
+      im: -self.im,
+    }
+  }
+}
+
+impl From<crate::Point> for Complex {
+  fn from(point: crate::Point) -> Complex {
+    Complex {
+      re: point.x,
+      im: point.y,
+    }
+  }
+}
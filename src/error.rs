@@ -0,0 +1,44 @@
+// *********************************************************************************************************************
+// Errors reported to JavaScript
+//
+// Each error is passed to JavaScript as a string that starts with a fixed description of its kind, so that callers can
+// tell the kinds apart
+// *********************************************************************************************************************
+
+use std::fmt;
+
+use wasm_bindgen::JsValue;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FractalError {
+  InvalidDimensions(String), // The canvas, or part of it, cannot be drawn
+  InvalidPalette(String),    // A colour map or colour is malformed
+  InvalidRange(String),      // An axis range is empty, reversed or not finite
+  InvalidParameter(String),  // Any other argument is out of range or malformed
+  CanvasError(String),       // The browser failed to draw the image
+}
+
+impl fmt::Display for FractalError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      FractalError::InvalidDimensions(msg) => write!(f, "Invalid dimensions: {}", msg),
+      FractalError::InvalidPalette(msg) => write!(f, "Invalid colour map: {}", msg),
+      FractalError::InvalidRange(msg) => write!(f, "Invalid range: {}", msg),
+      FractalError::InvalidParameter(msg) => write!(f, "Invalid parameter: {}", msg),
+      FractalError::CanvasError(msg) => write!(f, "Canvas error: {}", msg),
+    }
+  }
+}
+
+impl From<FractalError> for JsValue {
+  fn from(err: FractalError) -> JsValue {
+    JsValue::from_str(&err.to_string())
+  }
+}
+
+// Errors raised by web_sys calls are JavaScript exceptions, which are usually either strings or Error objects
+impl From<JsValue> for FractalError {
+  fn from(err: JsValue) -> FractalError {
+    FractalError::CanvasError(err.as_string().unwrap_or_else(|| format!("{:?}", err)))
+  }
+}
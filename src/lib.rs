@@ -6,64 +6,307 @@
 // For a given point on the Mandelbrot set, calculate the corresponding Julia set
 // *********************************************************************************************************************
 
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_derive::Deserialize;
+
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::{Clamped, JsValue};
 
 use web_sys::{CanvasRenderingContext2d, ImageData};
 
+mod complex;
+mod error;
+mod png;
+#[cfg(feature = "simd")]
+mod simd;
+use complex::Complex;
+pub use error::FractalError;
+
+// Default (and minimum) value of |z|^2 beyond which a point is considered to have escaped
 const BAILOUT: f64 = 4.0;
 
+// Squared distance within which two values of z in the same orbit are considered to be equal
+const PERIODICITY_EPSILON: f64 = 1e-24;
+
+// The distance estimate is only accurate once |z| is large, so points are iterated well beyond the normal bailout
+const DISTANCE_BAILOUT: f64 = 1.0e6;
+
+// The three roots of z^3 - 1 and the squared distance within which a point is considered to have converged to a root
+const NEWTON_ROOTS: [(f64, f64); 3] = [
+  (1.0, 0.0),
+  (-0.5, 0.866_025_403_784_438_6),
+  (-0.5, -0.866_025_403_784_438_6),
+];
+const NEWTON_EPSILON: f64 = 1e-12;
+
+// Stripe averages vary smoothly only when orbits are followed well beyond the normal bailout
+const STRIPE_BAILOUT: f64 = 1.0e4;
+
+// The potential tends to its limiting value as |z| grows, so points are iterated well beyond the normal bailout
+const POTENTIAL_BAILOUT: f64 = 1.0e10;
+
+// Buddhabrot samples are taken from this square, which contains the whole Mandelbrot Set
+const BUDDHABROT_SAMPLE_RANGE: f64 = 2.0;
+const BUDDHABROT_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+// Centre and squared radius of disks lying inside the two period-3 bulbs on the main cardioid and the main cardioid of
+// the period-3 minibrot on the real axis
+const PERIOD_3_DISKS: [(f64, f64, f64); 3] = [
+  (
+    -0.122_561_166_876_653_6,
+    0.744_861_766_619_744_2,
+    0.09 * 0.09,
+  ),
+  (
+    -0.122_561_166_876_653_6,
+    -0.744_861_766_619_744_2,
+    0.09 * 0.09,
+  ),
+  (-1.754_877_666_246_693, 0.0, 0.0045 * 0.0045),
+];
+
+// Every Julia Set lies within this square, and auto-framed views are enlarged by this fraction of their size
+const JULIA_SAMPLE_RANGE: f64 = 2.0;
+const JULIA_FRAME_MARGIN: f64 = 0.05;
+
+// Suggested iteration limits never fall below the minimum, and rise by ITERS_PER_ZOOM_LOG for every factor of e by which
+// the X axis range is narrower than 1.  So a range 1e-10 wide gets about 100 * ln(1e10) = 2302 iterations
+const MIN_SUGGESTED_ITERS: u32 = 100;
+const ITERS_PER_ZOOM_LOG: f64 = 100.0;
+
+// The whole of the Mandelbrot Set lies within this box, which is sampled when estimating the area of the set
+const MANDEL_BOX_X: (f64, f64) = (-2.0, 0.5);
+const MANDEL_BOX_Y: (f64, f64) = (-1.25, 1.25);
+
+// Number of logistic map iterations discarded before the Lyapunov exponent is measured, so that the starting value has
+// no influence on the result
+const LYAPUNOV_WARMUP: u32 = 50;
+
+// A minibrot search samples a (2n + 1) x (2n + 1) grid of points, then refines each candidate nucleus with at most this
+// many steps of Newton's method
+const MINIBROT_SEARCH_STEPS: i32 = 8;
+const MINIBROT_NEWTON_STEPS: u32 = 64;
+
+// Ordered dithering thresholds, which spread the 16 steps between adjacent colour map entries evenly over each 4 x 4
+// block of pixels
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// Largest image buffer that will be allocated, which is 16384 x 16384 pixels at 4 bytes per pixel
+const MAX_IMAGE_BYTES: u64 = 1 << 30;
+
+// Geometric shapes used to colour a point by how closely its orbit approaches the shape
+// From JavaScript these are written as {"Point": {"x": 0.0, "y": 0.0}}, "Cross" or {"Circle": 1.0}
+#[derive(Deserialize)]
+enum OrbitTrap {
+  Point(Point), // A single point
+  Cross,        // The x and y axes
+  Circle(f64),  // A circle of the given radius centred on the origin
+}
+
+// How iteration values are mapped onto the colour map
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub enum ColorScale {
+  Linear,      // Each iteration uses the next colour map entry
+  Logarithmic, // Spread ln(1 + iters) across the whole colour map to show more detail near the set boundary
+}
+
+// A colour at a position between 0 and 1 along a gradient
+// From JavaScript these are written as {"pos": 0.5, "r": 255, "g": 128, "b": 0}
+#[derive(Deserialize)]
+struct GradientStop {
+  pos: f64,
+  r: u32,
+  g: u32,
+  b: u32,
+}
+
+// Small, fast xorshift pseudo-random number generator
+// The quality of the numbers does not matter for sampling fractals, and unlike the browser's RNG, it needs no calls
+// out to JavaScript
+struct XorShift64 {
+  state: u64,
+}
+
+impl XorShift64 {
+  fn new(seed: u64) -> XorShift64 {
+    // The state must never be zero, otherwise every number generated would be zero
+    XorShift64 { state: seed.max(1) }
+  }
+
+  fn next_u64(&mut self) -> u64 {
+    self.state ^= self.state << 13;
+    self.state ^= self.state >> 7;
+    self.state ^= self.state << 17;
+    self.state
+  }
+
+  // Return a number in the range [min, max)
+  fn next_in_range(&mut self, min: f64, max: f64) -> f64 {
+    let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+    min + unit * (max - min)
+  }
+}
+
+// Which pixels are drawn; masked out pixels are made fully transparent
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub enum RenderMask {
+  All,          // Draw every pixel
+  ExteriorOnly, // Only draw pixels that escape before max_iters
+  InteriorOnly, // Only draw pixels that reach max_iters
+}
+
+// A rectangular region of the canvas measured in pixels
+#[derive(Clone, Copy)]
+struct Region {
+  x: u32,
+  y: u32,
+  width: u32,
+  height: u32,
+}
+
+// Conditions under which the escape time calculation stops
+#[derive(Clone, Copy)]
+struct EscapeParams {
+  max_iters: u32,           // Stop after this many iterations
+  bailout: f64,             // Escape when |z|^2 exceeds this value
+  detect_periodicity: bool, // Stop as soon as the orbit is found to be periodic?
+  check_period_3: bool, // Also skip points in the largest period-3 components of the Mandelbrot Set?
+}
+
+impl EscapeParams {
+  fn new(max_iters: u32) -> EscapeParams {
+    EscapeParams {
+      max_iters,
+      bailout: BAILOUT,
+      detect_periodicity: false,
+      check_period_3: false,
+    }
+  }
+}
+
+// How render_fractal turns iteration values into RGBA byte data
+#[derive(Clone, Copy)]
+struct RenderOptions {
+  is_little_endian: bool,            // Is the processor little endian?
+  smooth: bool,                      // Interpolate using fractional iteration counts?
+  wrap_palette: bool,                // Repeat the colour map instead of clamping?
+  use_symmetry: bool,                // Mirror the image about the x axis when possible?
+  preserve_aspect: bool,             // Keep the canvas aspect ratio?
+  color_scale: ColorScale,           // Iteration to colour mapping
+  mask: RenderMask,                  // Which pixels are drawn
+  alpha: u8,                         // Opacity of the pixels that are drawn
+  interior_colour: Option<[u32; 3]>, // Colour of points inside the set
+  refine_boundary: bool,             // Recalculate pixels on the edge of the set?
+  dither: bool,                      // Dither between adjacent colour map entries?
+}
+
+impl RenderOptions {
+  fn new(is_little_endian: bool) -> RenderOptions {
+    RenderOptions {
+      is_little_endian,
+      smooth: false,
+      wrap_palette: false,
+      use_symmetry: false,
+      preserve_aspect: false,
+      color_scale: ColorScale::Linear,
+      mask: RenderMask::All,
+      alpha: 0xff,
+      interior_colour: None,
+      refine_boundary: false,
+      dither: false,
+    }
+  }
+}
+
+#[derive(Clone, Copy)]
 enum FractalType {
   Mandelbrot,
-  Julia,
+  Julia(Point), // Value of c, taken from the mouse pointer location
+  BurningShip,
+  Tricorn,
+  Multibrot(u32),
+  Phoenix(Point), // Complex parameter p multiplying the previous value of z
 }
 
+#[derive(Clone, Copy)]
 #[wasm_bindgen]
 pub struct Dimensions {
   width: u32,
   height: u32,
 }
 
+#[derive(Clone, Copy)]
 #[wasm_bindgen]
 pub struct Range {
   min: f64,
   max: f64,
 }
 
+#[derive(Clone, Copy)]
 #[wasm_bindgen]
 pub struct AxesRanges {
   x_range: Range,
   y_range: Range,
 }
 
+#[derive(Clone, Copy, Deserialize)]
 #[wasm_bindgen]
 pub struct Point {
   x: f64,
   y: f64,
 }
 
+// Summary of the iteration counts in a rendered image
+#[wasm_bindgen]
+pub struct RenderStats {
+  pub min_iters: u32,            // Lowest iteration count of any pixel
+  pub max_iters_hit: u32,        // Highest iteration count of any pixel
+  pub avg_iters: f64,            // Mean iteration count across all pixels
+  pub interior_pixel_count: u32, // Number of pixels that reached max_iters
+}
+
 #[wasm_bindgen]
 pub fn gen_struct_dimensions(width: u32, height: u32) -> Dimensions {
   Dimensions { width, height }
 }
 
+// Ranges must run from a smaller minimum to a larger maximum, otherwise the image would be mirrored (or, for a range
+// of zero width, could not be drawn at all)
+fn valid_range(min: f64, max: f64, name: &str) -> Result<Range, FractalError> {
+  if min.is_finite() && max.is_finite() && max > min {
+    Ok(Range { min, max })
+  } else {
+    Err(FractalError::InvalidRange(format!(
+      "{} max ({}) must be greater than min ({})",
+      name, max, min
+    )))
+  }
+}
+
+// Range values are always passed in (min, max) order
 #[wasm_bindgen]
-pub fn gen_struct_range(max: f64, min: f64) -> Range {
-  Range { max, min }
+pub fn gen_struct_range(min: f64, max: f64) -> Result<Range, FractalError> {
+  valid_range(min, max, "range")
 }
 
 #[wasm_bindgen]
-pub fn gen_struct_axes_ranges(x_max: f64, x_min: f64, y_max: f64, y_min: f64) -> AxesRanges {
-  AxesRanges {
-    x_range: Range {
-      max: x_max,
-      min: x_min,
-    },
-    y_range: Range {
-      max: y_max,
-      min: y_min,
-    },
-  }
+pub fn gen_struct_axes_ranges(
+  x_min: f64,
+  x_max: f64,
+  y_min: f64,
+  y_max: f64,
+) -> Result<AxesRanges, FractalError> {
+  Ok(AxesRanges {
+    x_range: valid_range(x_min, x_max, "x axis")?,
+    y_range: valid_range(y_min, y_max, "y axis")?,
+  })
 }
 
 #[wasm_bindgen]
@@ -71,6 +314,59 @@ pub fn gen_struct_point(x: f64, y: f64) -> Point {
   Point { x, y }
 }
 
+// Getters allow JavaScript to read back the contents of structs returned by Rust
+#[wasm_bindgen]
+impl Dimensions {
+  #[wasm_bindgen(getter)]
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+}
+
+#[wasm_bindgen]
+impl Range {
+  #[wasm_bindgen(getter)]
+  pub fn min(&self) -> f64 {
+    self.min
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn max(&self) -> f64 {
+    self.max
+  }
+}
+
+#[wasm_bindgen]
+impl AxesRanges {
+  #[wasm_bindgen(getter)]
+  pub fn x_range(&self) -> Range {
+    self.x_range
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn y_range(&self) -> Range {
+    self.y_range
+  }
+}
+
+#[wasm_bindgen]
+impl Point {
+  #[wasm_bindgen(getter)]
+  pub fn x(&self) -> f64 {
+    self.x
+  }
+
+  #[wasm_bindgen(getter)]
+  pub fn y(&self) -> f64 {
+    self.y
+  }
+}
+
 // *********************************************************************************************************************
 // PRIVATE API
 // *********************************************************************************************************************
@@ -82,186 +378,6512 @@ fn draw_fractal(
   ctx: &CanvasRenderingContext2d,
   canvas: Dimensions,      // Canvas dimensions
   axes_ranges: AxesRanges, // Extent of X and Y axes
-  mouse_loc: Point,        // Mouse pointer location on Mandelbrot set
-  max_iters: u32,          // Stop after this many iterations
+  params: EscapeParams,    // Conditions under which the escape time calculation stops
   c_map: JsValue,          // Selected colour map
-  is_little_endian: bool,  // Is the processor little endian?
-  f_type: FractalType,
-) -> Result<(), JsValue> {
-  // Deserialize the colour map
-  let colour_map: Vec<Vec<u32>> = JsValue::into_serde(&c_map).unwrap();
-  let mut image_data = Vec::new();
+  f_type: FractalType,     // Which fractal to calculate
+  options: RenderOptions,  // How iteration values are turned into pixels
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
 
-  // Build partial functions to scale (x,y) canvas locations to the fractal's coordinate space
-  let scale = move |scale_factor: f64, length: f64| move |pos: f64| scale_factor * (pos / length);
-  let scale_x = scale(
-    axes_ranges.x_range.max - axes_ranges.x_range.min,
-    (canvas.width - 1) as f64,
-  );
-  let scale_y = scale(
-    axes_ranges.y_range.max - axes_ranges.y_range.min,
-    (canvas.height - 1) as f64,
-  );
+  let colour_map = parse_colour_map(&c_map)?;
+  let axes_ranges = if options.preserve_aspect {
+    fit_to_aspect(
+      axes_ranges.x_range.min,
+      axes_ranges.x_range.max,
+      axes_ranges.y_range.min,
+      axes_ranges.y_range.max,
+      &canvas,
+    )
+  } else {
+    axes_ranges
+  };
+  let image_data = render_fractal(&canvas, &axes_ranges, params, &colour_map, f_type, &options);
+
+  put_image(ctx, &image_data, &canvas)
+}
+
+/***********************************************************************************************************************
+ * Calculate a fractal and return the resulting RGBA byte data without drawing it
+ * With an iteration limit of 0, no point is ever iterated, so none can be shown to lie inside the set.  Every pixel is
+ * then treated as an exterior point and takes the first colour map entry
+ */
+fn render_fractal(
+  canvas: &Dimensions,      // Canvas dimensions
+  axes_ranges: &AxesRanges, // Extent of X and Y axes
+  params: EscapeParams,     // Conditions under which the escape time calculation stops
+  colour_map: &[Vec<u32>],  // Selected colour map
+  f_type: FractalType,      // Which fractal to calculate
+  options: &RenderOptions,  // How iteration values are turned into pixels
+) -> Vec<u8> {
+  let RenderOptions {
+    is_little_endian,
+    smooth,
+    wrap_palette,
+    use_symmetry,
+    color_scale,
+    mask,
+    alpha,
+    interior_colour,
+    refine_boundary,
+    dither,
+    ..
+  } = *options;
+
+  // The Mandelbrot Set is symmetrical about the x axis, so if the y axis range is also symmetrical, only the top half of
+  // the image needs to be calculated
+  let mirror_rows = use_symmetry
+    && !refine_boundary
+    && matches!(f_type, FractalType::Mandelbrot)
+    && axes_ranges.y_range.min == -axes_ranges.y_range.max;
+
+  if params.max_iters == 0 {
+    let first_colour = palette_rgba(&colour_map[0]);
+    let mut image_data = vec![0u8; (canvas.width * canvas.height * 4) as usize];
+
+    for pixel_data in image_data.chunks_exact_mut(4) {
+      write_rgba(pixel_data, &first_colour, is_little_endian);
+    }
+
+    apply_mask(
+      &mut image_data,
+      std::iter::repeat(false),
+      mask,
+      is_little_endian,
+    );
+    set_alpha(&mut image_data, alpha, is_little_endian);
+    return image_data;
+  }
+
+  // Each colour map entry may have a 4th value giving its opacity, but the interior colour is always opaque
+  let interior_rgba = interior_colour.map(|[red, green, blue]| [red, green, blue, 0xff]);
+
+  // Return the colour map entry for an integer iteration value
+  let iter_colour = |iters: usize| {
+    if let (Some(colour), true) = (interior_rgba, iters >= params.max_iters as usize) {
+      return colour;
+    }
+
+    let idx = match color_scale {
+      ColorScale::Linear => palette_index(iters, params.max_iters, colour_map.len(), wrap_palette),
+      ColorScale::Logarithmic => {
+        let idx = log_palette_value(iters as f64, params.max_iters, colour_map.len()) as usize;
+        palette_index(idx, params.max_iters, colour_map.len(), false)
+      }
+    };
+    palette_rgba(&colour_map[idx])
+  };
+
+  // Return the colour map entry nearest to an iteration value after it has been offset by a dithering threshold
+  let dithered_colour = |value: f64, threshold: f64| {
+    if let (Some(colour), true) = (interior_rgba, value >= params.max_iters as f64) {
+      return colour;
+    }
+
+    let (position, wrap) = match color_scale {
+      ColorScale::Linear => (value, wrap_palette),
+      ColorScale::Logarithmic => (
+        log_palette_value(value, params.max_iters, colour_map.len()),
+        false,
+      ),
+    };
+    let mut idx = (position + threshold).round().max(0.0) as usize;
+
+    // Dithering must never turn an exterior point into an interior one
+    if value < params.max_iters as f64 {
+      idx = idx.min(params.max_iters.saturating_sub(1) as usize);
+    }
+
+    palette_rgba(&colour_map[palette_index(idx, params.max_iters, colour_map.len(), wrap)])
+  };
+
+  // Return the interpolated colour for a fractional iteration value
+  let smooth_colour = |value: f64| match (color_scale, interior_rgba) {
+    (_, Some(colour)) if value >= params.max_iters as f64 => colour,
+    (ColorScale::Linear, _) => interpolate_rgba(colour_map, value, params.max_iters, wrap_palette),
+    (ColorScale::Logarithmic, _) => interpolate_rgba(
+      colour_map,
+      log_palette_value(value, params.max_iters, colour_map.len()),
+      params.max_iters,
+      false,
+    ),
+  };
+
+  let compute_iter_buffer = || {
+    let mut iter_buffer = compute_fractal_buffer(canvas, axes_ranges, &params, &f_type);
+
+    if refine_boundary {
+      refine_boundary_pixels(canvas, axes_ranges, &params, &f_type, &mut iter_buffer);
+    }
+
+    iter_buffer
+  };
 
   // Here's where the heavy lifting happens...
-  for iy in 0..canvas.height {
-    for ix in 0..canvas.width {
-      let this_coord = Point {
-        x: axes_ranges.x_range.min + scale_x(ix as f64),
-        y: axes_ranges.y_range.min + scale_y(iy as f64),
+  // Masking and dithering need to know where each pixel is, so in these cases the iteration values are buffered first
+  let mut image_data = match (smooth, mask) {
+    _ if dither => {
+      let values: Vec<f64> = if smooth {
+        map_pixels(canvas, axes_ranges, |this_coord| {
+          smooth_fractal_iter(&f_type, this_coord, &params)
+        })
+      } else {
+        compute_iter_buffer()
+          .iter()
+          .map(|&iters| iters as f64)
+          .collect()
       };
+      let mut image_data =
+        render_buffer_dithered(&values, canvas, is_little_endian, dithered_colour);
+      let interior = values.iter().map(|&value| value >= params.max_iters as f64);
 
-      // Determine the colour of the current pixel
-      let this_colour = &colour_map[match f_type {
-        FractalType::Mandelbrot => mandel_iter(&this_coord, &max_iters),
-        FractalType::Julia => escape_time_mj(&mouse_loc, this_coord, &max_iters),
-      }];
-
-      // Insert RGBA byte data into the image_data vector according to the processor's endianness
-      if is_little_endian {
-        image_data.push(this_colour[0] as u8); // Red
-        image_data.push(this_colour[1] as u8); // Green
-        image_data.push(this_colour[2] as u8); // Blue
-        image_data.push(0xff); // Hard-coded alpha value
-      } else {
-        image_data.push(0xff); // Hard-coded alpha value
-        image_data.push(this_colour[2] as u8); // Blue
-        image_data.push(this_colour[1] as u8); // Green
-        image_data.push(this_colour[0] as u8); // Red
-      }
+      apply_mask(&mut image_data, interior, mask, is_little_endian);
+      image_data
+    }
+    (true, RenderMask::All) => render_pixels(
+      canvas,
+      axes_ranges,
+      is_little_endian,
+      mirror_rows,
+      |this_coord| smooth_colour(smooth_fractal_iter(&f_type, this_coord, &params)),
+    ),
+    (false, RenderMask::All) if mirror_rows => {
+      render_pixels(canvas, axes_ranges, is_little_endian, true, |this_coord| {
+        iter_colour(fractal_iter(&f_type, this_coord, &params))
+      })
     }
+    (true, _) => {
+      let values = map_pixels(canvas, axes_ranges, |this_coord| {
+        smooth_fractal_iter(&f_type, this_coord, &params)
+      });
+      let mut image_data = render_buffer(&values, is_little_endian, |&value| smooth_colour(value));
+      let interior = values.iter().map(|&value| value >= params.max_iters as f64);
+
+      apply_mask(&mut image_data, interior, mask, is_little_endian);
+      image_data
+    }
+    (false, _) => {
+      let iter_buffer = compute_iter_buffer();
+      let mut image_data =
+        render_buffer(&iter_buffer, is_little_endian, |&iters| iter_colour(iters));
+      let interior = iter_buffer
+        .iter()
+        .map(|&iters| iters >= params.max_iters as usize);
+
+      apply_mask(&mut image_data, interior, mask, is_little_endian);
+      image_data
+    }
+  };
+
+  set_alpha(&mut image_data, alpha, is_little_endian);
+  image_data
+}
+
+/***********************************************************************************************************************
+ * Scale the opacity of every pixel by alpha / 255
+ * Pixels from colour map entries without their own opacity are fully opaque, so these simply take the value of alpha
+ */
+fn set_alpha(image_data: &mut [u8], alpha: u8, is_little_endian: bool) {
+  if alpha == 0xff {
+    return;
   }
 
-  let image_data = ImageData::new_with_u8_clamped_array_and_sh(
-    Clamped(&mut image_data),
-    canvas.width,
-    canvas.height,
-  )?;
-  ctx.put_image_data(&image_data, 0.0, 0.0)
+  let alpha_idx = if is_little_endian { 3 } else { 0 };
+
+  for pixel_data in image_data.chunks_exact_mut(4) {
+    pixel_data[alpha_idx] = ((pixel_data[alpha_idx] as u32 * alpha as u32 + 127) / 255) as u8;
+  }
 }
 
 /***********************************************************************************************************************
- * Return the iteration value of a particular pixel in the Mandelbrot set
- * This calculation bails out early if the current point is located within the main cardioid or the period-2 bulb
+ * Make the pixels excluded by the render mask fully transparent
+ * The interior flags must be supplied in the same row-major order as the RGBA byte data
  */
-fn mandel_iter(loc: &Point, max_iters: &u32) -> usize {
-  if mandel_early_bailout(&loc) {
-    *max_iters as usize
-  } else {
-    escape_time_mj(&loc, Point { x: 0.0, y: 0.0 }, max_iters)
+fn apply_mask<I>(image_data: &mut [u8], interior: I, mask: RenderMask, is_little_endian: bool)
+where
+  I: Iterator<Item = bool>,
+{
+  // The alpha value is the last byte of each pixel on a little endian processor, else it is the first
+  let alpha_idx = if is_little_endian { 3 } else { 0 };
+
+  for (pixel_data, is_interior) in image_data.chunks_exact_mut(4).zip(interior) {
+    let is_visible = match mask {
+      RenderMask::All => true,
+      RenderMask::ExteriorOnly => !is_interior,
+      RenderMask::InteriorOnly => is_interior,
+    };
+
+    if !is_visible {
+      pixel_data[alpha_idx] = 0;
+    }
   }
 }
 
 /***********************************************************************************************************************
- * Calculate whether the current point lies within the Mandelbrot Set's main cardioid or the period-2 bulb
- * If it does, then we can bail out early
+ * Calculate the iteration value of every pixel for the given fractal type and return the values in row-major order
+ * This does no rendering and does not touch any JavaScript objects, so it can be run natively
  */
-fn mandel_early_bailout(loc: &Point) -> bool {
-  is_in_main_cardioid(&loc, sum_of_squares(loc.x - 0.25, loc.y)) || is_in_period_2_bulb(&loc)
+fn compute_fractal_buffer(
+  canvas: &Dimensions,      // Canvas dimensions
+  axes_ranges: &AxesRanges, // Extent of X and Y axes
+  params: &EscapeParams,    // Conditions under which the escape time calculation stops
+  f_type: &FractalType,
+) -> Vec<usize> {
+  // The SIMD path does not support periodicity detection
+  #[cfg(feature = "simd")]
+  {
+    if !params.detect_periodicity {
+      match f_type {
+        FractalType::Mandelbrot | FractalType::Julia(_) => {
+          return compute_buffer_simd(canvas, axes_ranges, params, f_type);
+        }
+        _ => (),
+      }
+    }
+  }
+
+  map_pixels(canvas, axes_ranges, |this_coord| {
+    fractal_iter(f_type, this_coord, params)
+  })
 }
 
-fn is_in_main_cardioid(loc: &Point, temp: f64) -> bool {
-  temp * (temp + loc.x - 0.25) <= (loc.y * loc.y) / 4.0
+/***********************************************************************************************************************
+ * Recalculate the pixels that reached the iteration limit but have a neighbour outside the set, using twice the
+ * iteration limit
+ * These are the pixels most likely to have been wrongly treated as interior points.  Those that escape within the higher
+ * limit are given the highest exterior iteration value.  This escape time is never lower than that of any exterior
+ * neighbour, so the refined pixels do not stand out from the boundary around them.  All other pixels are left unchanged
+ */
+fn refine_boundary_pixels(
+  canvas: &Dimensions,      // Canvas dimensions
+  axes_ranges: &AxesRanges, // Extent of X and Y axes
+  params: &EscapeParams,    // Conditions under which the escape time calculation stops
+  f_type: &FractalType,
+  iter_buffer: &mut [usize],
+) {
+  let max_iters = params.max_iters as usize;
+
+  if max_iters == 0 {
+    return;
+  }
+
+  let (width, height) = (canvas.width as usize, canvas.height as usize);
+  let to_coord = pixel_to_point(canvas, axes_ranges);
+  let refined_params = EscapeParams {
+    max_iters: params.max_iters.saturating_mul(2),
+    ..*params
+  };
+
+  let is_exterior = |ix: usize, iy: usize| iter_buffer[iy * width + ix] < max_iters;
+  let boundary_pixels: Vec<(usize, usize)> = (0..height)
+    .flat_map(|iy| (0..width).map(move |ix| (ix, iy)))
+    .filter(|&(ix, iy)| {
+      !is_exterior(ix, iy)
+        && ((ix > 0 && is_exterior(ix - 1, iy))
+          || (ix + 1 < width && is_exterior(ix + 1, iy))
+          || (iy > 0 && is_exterior(ix, iy - 1))
+          || (iy + 1 < height && is_exterior(ix, iy + 1)))
+    })
+    .collect();
+
+  for (ix, iy) in boundary_pixels {
+    let this_coord = to_coord(ix as u32, iy as u32);
+
+    if fractal_iter(f_type, this_coord, &refined_params) < refined_params.max_iters as usize {
+      iter_buffer[iy * width + ix] = max_iters - 1;
+    }
+  }
 }
 
-fn is_in_period_2_bulb(loc: &Point) -> bool {
-  sum_of_squares(loc.x + 1.0, loc.y) <= 0.0625
+/***********************************************************************************************************************
+ * Calculate the iteration value of every pixel in either the Mandelbrot Set or a Julia Set, simd::LANES pixels at a
+ * time
+ */
+#[cfg(feature = "simd")]
+fn compute_buffer_simd(
+  canvas: &Dimensions,      // Canvas dimensions
+  axes_ranges: &AxesRanges, // Extent of X and Y axes
+  params: &EscapeParams,    // Conditions under which the escape time calculation stops
+  f_type: &FractalType,
+) -> Vec<usize> {
+  let julia_c = match *f_type {
+    FractalType::Julia(c) => Some(c),
+    _ => None,
+  };
+  let points = map_pixels(canvas, axes_ranges, |this_coord| this_coord);
+  let mut values = Vec::with_capacity(points.len());
+
+  for batch in points.chunks(simd::LANES) {
+    let c_0 = julia_c.unwrap_or(Point { x: 0.0, y: 0.0 });
+    let mut c = ([c_0.x; simd::LANES], [c_0.y; simd::LANES]);
+    let mut z = ([0.0; simd::LANES], [0.0; simd::LANES]);
+
+    // Unused lanes in the final batch are skipped and their results discarded
+    let mut skip = [true; simd::LANES];
+
+    for (lane, point) in batch.iter().enumerate() {
+      if julia_c.is_some() {
+        z.0[lane] = point.x;
+        z.1[lane] = point.y;
+        skip[lane] = false;
+      } else {
+        c.0[lane] = point.x;
+        c.1[lane] = point.y;
+        skip[lane] = mandel_early_bailout_extended(point, params.check_period_3);
+      }
+    }
+
+    let iter_counts = simd::escape_time_simd(c.0, c.1, z.0, z.1, skip, params);
+    values.extend(
+      iter_counts[..batch.len()]
+        .iter()
+        .map(|&iters| iters as usize),
+    );
+  }
+
+  values
 }
 
 /***********************************************************************************************************************
- * Common escape time algorithm for calculating both the Mandelbrot and Julia Sets
+ * Reuse the iteration values of a previous render after the view has been panned
+ * If the new axes ranges have the same extent as the old ones and are offset by a whole number of pixels, then the
+ * values in the overlapping area are copied and only the newly exposed pixels are calculated.  Otherwise, every pixel
+ * is recalculated
  */
-fn escape_time_mj(mandel_point: &Point, mut start_val: Point, max_iters: &u32) -> usize {
-  let mut iter_count: u32 = 0;
+fn shift_iter_buffer<F>(
+  canvas: &Dimensions,   // Canvas dimensions
+  old_axes: &AxesRanges, // Extent of X and Y axes for the previous render
+  old_buffer: &[usize],  // Iteration values from the previous render
+  new_axes: &AxesRanges, // Extent of X and Y axes for this render
+  pixel_value: F,        // Calculates the iteration value of a point
+) -> Vec<usize>
+where
+  F: Fn(Point) -> usize,
+{
+  const TOLERANCE: f64 = 1e-6;
 
-  // Count the number of iterations needed before the value at the current location either escapes to infinity or hits
-  // the iteration limit
-  while (sum_of_squares(start_val.x, start_val.y) <= BAILOUT) && iter_count < *max_iters {
-    let new_x = mandel_point.x + diff_of_squares(start_val.x, start_val.y);
-    let new_y = mandel_point.y + (2.0 * start_val.x * start_val.y);
-    start_val.x = new_x;
-    start_val.y = new_y;
-    iter_count += 1;
+  let span = |range: &Range| range.max - range.min;
+  let pixel_width = span(&new_axes.x_range) / (canvas.width.max(2) - 1) as f64;
+  let pixel_height = span(&new_axes.y_range) / (canvas.height.max(2) - 1) as f64;
+
+  // How many pixels has the view moved?
+  let dx = (new_axes.x_range.min - old_axes.x_range.min) / pixel_width;
+  let dy = (new_axes.y_range.min - old_axes.y_range.min) / pixel_height;
+
+  let same_extent = (span(&new_axes.x_range) - span(&old_axes.x_range)).abs()
+    <= pixel_width * TOLERANCE
+    && (span(&new_axes.y_range) - span(&old_axes.y_range)).abs() <= pixel_height * TOLERANCE;
+  let whole_pixels = (dx - dx.round()).abs() <= TOLERANCE && (dy - dy.round()).abs() <= TOLERANCE;
+
+  if !same_extent || !whole_pixels || old_buffer.len() != (canvas.width * canvas.height) as usize {
+    return map_pixels(canvas, new_axes, pixel_value);
   }
 
-  iter_count as usize
+  let (dx, dy) = (dx.round() as i64, dy.round() as i64);
+  let (width, height) = (canvas.width as i64, canvas.height as i64);
+  let to_coord = pixel_to_point(canvas, new_axes);
+  let mut values = Vec::with_capacity(old_buffer.len());
+
+  for iy in 0..height {
+    for ix in 0..width {
+      let (old_ix, old_iy) = (ix + dx, iy + dy);
+
+      values.push(
+        if (0..width).contains(&old_ix) && (0..height).contains(&old_iy) {
+          old_buffer[(old_iy * width + old_ix) as usize]
+        } else {
+          pixel_value(to_coord(ix as u32, iy as u32))
+        },
+      );
+    }
+  }
+
+  values
 }
 
 /***********************************************************************************************************************
- * Utility functions
+ * Deserialize a value passed in from JavaScript, returning the supplied error if this is not possible
  */
-fn sum_of_squares(val1: f64, val2: f64) -> f64 {
-  val1 * val1 + val2 * val2
+fn from_js_value<T: DeserializeOwned>(val: &JsValue, err: FractalError) -> Result<T, FractalError> {
+  #[allow(deprecated)]
+  val.into_serde().map_err(|_| err)
 }
 
-fn diff_of_squares(val1: f64, val2: f64) -> f64 {
-  val1 * val1 - val2 * val2
+/***********************************************************************************************************************
+ * Serialize a value to be returned to JavaScript
+ */
+fn to_js_value<T: Serialize>(val: &T) -> Result<JsValue, JsValue> {
+  #[allow(deprecated)]
+  JsValue::from_serde(val).map_err(|err| JsValue::from_str(&err.to_string()))
 }
 
-// *********************************************************************************************************************
-// PUBLIC API
-// *********************************************************************************************************************
+/***********************************************************************************************************************
+ * Check that the canvas has at least one pixel in each direction and that its image data can be safely allocated
+ */
+fn validate_dimensions(canvas: &Dimensions) -> Result<(), FractalError> {
+  if canvas.width == 0 || canvas.height == 0 {
+    return Err(FractalError::InvalidDimensions(format!(
+      "canvas width ({}) and height ({}) must both be greater than zero",
+      canvas.width, canvas.height
+    )));
+  }
 
-#[wasm_bindgen]
-extern "C" {
-  #[wasm_bindgen(js_namespace = console)]
-  fn log(s: String);
+  let byte_count = canvas.width as u64 * canvas.height as u64 * 4;
+
+  if byte_count > MAX_IMAGE_BYTES {
+    Err(FractalError::InvalidDimensions(format!(
+      "a {} x {} canvas needs {} bytes of image data, but the limit is {}",
+      canvas.width, canvas.height, byte_count, MAX_IMAGE_BYTES
+    )))
+  } else {
+    Ok(())
+  }
 }
 
 /***********************************************************************************************************************
- * Dummy entry point
+ * Deserialize and validate the colour map
+ * Every entry must contain at least the red, green and blue values, and may contain a 4th value giving its opacity
  */
-#[wasm_bindgen(start)]
-pub fn main() -> Result<(), JsValue> {
-  log("WASM initialising...".to_string());
-  Ok(())
+fn parse_colour_map(c_map: &JsValue) -> Result<Vec<Vec<u32>>, FractalError> {
+  let colour_map: Vec<Vec<u32>> = from_js_value(
+    c_map,
+    FractalError::InvalidPalette("expected Vec<Vec<u32>>".to_string()),
+  )?;
+
+  if colour_map.is_empty() {
+    return Err(FractalError::InvalidPalette(
+      "no colours supplied".to_string(),
+    ));
+  }
+
+  match colour_map.iter().position(|colour| colour.len() < 3) {
+    Some(idx) => Err(FractalError::InvalidPalette(format!(
+      "entry {} has fewer than 3 values",
+      idx
+    ))),
+    None => Ok(colour_map),
+  }
 }
 
 /***********************************************************************************************************************
- * Draw a Mandelbrot Set
+ * Validate an optional interior colour, which must hold exactly the red, green and blue values
  */
-#[wasm_bindgen]
-pub fn draw_mandel(
-  ctx: &CanvasRenderingContext2d,
-  canvas: Dimensions,      // Canvas dimensions
-  axes_ranges: AxesRanges, // Extent of axes ranges
-  max_iters: u32,          // Stop after this many iterations
-  c_map: JsValue,          // Selected colour map
-  is_little_endian: bool,  // Is the processor little endian?
-) -> Result<(), JsValue> {
-  draw_fractal(
-    ctx,
-    canvas,
-    axes_ranges,
-    Point { x: 0.0, y: 0.0 },
-    max_iters,
-    c_map,
-    is_little_endian,
-    FractalType::Mandelbrot,
-  )
+fn parse_interior_colour(
+  interior_color: Option<Vec<u8>>,
+) -> Result<Option<[u32; 3]>, FractalError> {
+  match interior_color.as_deref() {
+    None => Ok(None),
+    Some(&[r, g, b]) => Ok(Some([r as u32, g as u32, b as u32])),
+    Some(colour) => Err(FractalError::InvalidPalette(format!(
+      "interior colour has {} values instead of 3",
+      colour.len()
+    ))),
+  }
 }
 
 /***********************************************************************************************************************
- * Draw a Julia Set
+ * Validate a colour map supplied as a flat array of RGB bytes and return the number of colours it contains
  */
-#[wasm_bindgen]
-pub fn draw_julia(
-  ctx: &CanvasRenderingContext2d,
-  canvas: Dimensions,      // Canvas dimensions
-  axes_ranges: AxesRanges, // Extent of axes ranges
-  mouse_loc: Point,        // Mouse pointer coords on Mandelbrot set
-  max_iters: u32,          // Stop after this many iterations
-  c_map: JsValue,          // Selected colour map
-  is_little_endian: bool,  // Is the processor little endian?
-) -> Result<(), JsValue> {
-  draw_fractal(
-    ctx,
+fn flat_palette_len(palette: &[u8]) -> Result<usize, FractalError> {
+  if palette.is_empty() {
+    Err(FractalError::InvalidPalette(
+      "no colours supplied".to_string(),
+    ))
+  } else if !palette.len().is_multiple_of(3) {
+    Err(FractalError::InvalidPalette(
+      "length must be a multiple of 3".to_string(),
+    ))
+  } else {
+    Ok(palette.len() / 3)
+  }
+}
+
+/***********************************************************************************************************************
+ * Draw a fractal using a colour map supplied as a flat array of RGB bytes
+ * This avoids deserializing the colour map with serde every time the fractal is drawn
+ */
+fn draw_fractal_rgb(
+  ctx: &CanvasRenderingContext2d,
+  canvas: &Dimensions,      // Canvas dimensions
+  axes_ranges: &AxesRanges, // Extent of X and Y axes
+  params: EscapeParams,     // Conditions under which the escape time calculation stops
+  palette: &[u8],           // Selected colour map as [r0, g0, b0, r1, g1, b1, ...]
+  is_little_endian: bool,   // Is the processor little endian?
+  f_type: FractalType,
+) -> Result<(), FractalError> {
+  let palette_len = flat_palette_len(palette)?;
+
+  draw_pixels(ctx, canvas, axes_ranges, is_little_endian, |this_coord| {
+    let iters = fractal_iter(&f_type, this_coord, &params);
+    let idx = palette_index(iters, params.max_iters, palette_len, false) * 3;
+    [
+      palette[idx] as u32,
+      palette[idx + 1] as u32,
+      palette[idx + 2] as u32,
+    ]
+  })
+}
+
+/***********************************************************************************************************************
+ * Colour every pixel on the canvas using the supplied function, then write the resulting image to the canvas
+ */
+fn draw_pixels<F>(
+  ctx: &CanvasRenderingContext2d,
+  canvas: &Dimensions,      // Canvas dimensions
+  axes_ranges: &AxesRanges, // Extent of X and Y axes
+  is_little_endian: bool,   // Is the processor little endian?
+  pixel_colour: F,          // Returns the RGB colour for a point in the fractal's coordinate space
+) -> Result<(), FractalError>
+where
+  F: Fn(Point) -> [u32; 3],
+{
+  let image_data = render_pixels(canvas, axes_ranges, is_little_endian, false, pixel_colour);
+  put_image(ctx, &image_data, canvas)
+}
+
+/***********************************************************************************************************************
+ * Colour every pixel on the canvas using the supplied function and return the resulting RGBA byte data
+ * If mirror_rows is true, only the top half of the rows are calculated and the bottom half is a reflection of the top
+ */
+fn render_pixels<F, K>(
+  canvas: &Dimensions,      // Canvas dimensions
+  axes_ranges: &AxesRanges, // Extent of X and Y axes
+  is_little_endian: bool,   // Is the processor little endian?
+  mirror_rows: bool,        // Reflect the top half of the image into the bottom half?
+  pixel_colour: F,          // Returns the RGB colour for a point in the fractal's coordinate space
+) -> Vec<u8>
+where
+  F: Fn(Point) -> K,
+  K: AsRef<[u32]>,
+{
+  let mut image_data = vec![0u8; (canvas.width * canvas.height * 4) as usize];
+
+  render_pixels_into(
+    &mut image_data,
     canvas,
     axes_ranges,
-    mouse_loc,
-    max_iters,
-    c_map,
     is_little_endian,
-    FractalType::Julia,
-  )
+    mirror_rows,
+    pixel_colour,
+  );
+  image_data
+}
+
+/***********************************************************************************************************************
+ * Colour every pixel on the canvas using the supplied function, writing the RGBA byte data into an existing buffer
+ * The buffer must hold exactly width * height * 4 bytes
+ */
+fn render_pixels_into<F, K>(
+  image_data: &mut [u8],    // RGBA byte data for the whole canvas
+  canvas: &Dimensions,      // Canvas dimensions
+  axes_ranges: &AxesRanges, // Extent of X and Y axes
+  is_little_endian: bool,   // Is the processor little endian?
+  mirror_rows: bool,        // Reflect the top half of the image into the bottom half?
+  pixel_colour: F,          // Returns the RGB colour for a point in the fractal's coordinate space
+) where
+  F: Fn(Point) -> K,
+  K: AsRef<[u32]>,
+{
+  let to_coord = pixel_to_point(canvas, axes_ranges);
+  let row_len = (canvas.width * 4) as usize;
+  let computed_rows = if mirror_rows {
+    (canvas.height as usize).div_ceil(2)
+  } else {
+    canvas.height as usize
+  };
+
+  for (iy, row_data) in image_data
+    .chunks_exact_mut(row_len)
+    .take(computed_rows)
+    .enumerate()
+  {
+    draw_row(
+      row_data,
+      0,
+      iy as u32,
+      &to_coord,
+      &pixel_colour,
+      is_little_endian,
+    );
+  }
+
+  for iy in computed_rows..canvas.height as usize {
+    let mirror_start = (canvas.height as usize - 1 - iy) * row_len;
+    image_data.copy_within(mirror_start..mirror_start + row_len, iy * row_len);
+  }
+}
+
+/***********************************************************************************************************************
+ * Colour the pixels in a rectangular region of the canvas and return the resulting RGBA byte data
+ * Pixel locations are scaled as if they were part of the whole canvas, so that adjacent regions join up seamlessly
+ */
+fn render_region<F>(
+  canvas: &Dimensions,      // Canvas dimensions
+  axes_ranges: &AxesRanges, // Extent of X and Y axes
+  region: &Region,          // Part of the canvas to be calculated
+  is_little_endian: bool,   // Is the processor little endian?
+  pixel_colour: F,          // Returns the RGB colour for a point in the fractal's coordinate space
+) -> Vec<u8>
+where
+  F: Fn(Point) -> [u32; 3],
+{
+  let to_coord = pixel_to_point(canvas, axes_ranges);
+  let row_len = (region.width * 4) as usize;
+  let mut image_data = vec![0u8; row_len * region.height as usize];
+
+  for (iy, row_data) in image_data.chunks_exact_mut(row_len).enumerate() {
+    draw_row(
+      row_data,
+      region.x,
+      region.y + iy as u32,
+      &to_coord,
+      &pixel_colour,
+      is_little_endian,
+    );
+  }
+
+  image_data
+}
+
+/***********************************************************************************************************************
+ * Colour each value in a buffer of previously calculated pixel values and return the resulting RGBA byte data
+ */
+fn render_buffer<T, F, K>(values: &[T], is_little_endian: bool, value_colour: F) -> Vec<u8>
+where
+  F: Fn(&T) -> K,
+  K: AsRef<[u32]>,
+{
+  let mut image_data = vec![0u8; values.len() * 4];
+
+  for (value, pixel_data) in values.iter().zip(image_data.chunks_exact_mut(4)) {
+    write_rgba(pixel_data, value_colour(value).as_ref(), is_little_endian);
+  }
+
+  image_data
+}
+
+/***********************************************************************************************************************
+ * Convert a buffer of per-pixel values to RGBA byte data, offsetting each value by the ordered dithering threshold for
+ * its position on the canvas
+ * The thresholds lie between -0.5 and 0.5, so rounding a dithered value picks one of the two colour map entries either
+ * side of it in proportion to its fractional part
+ */
+fn render_buffer_dithered<F, K>(
+  values: &[f64],
+  canvas: &Dimensions,
+  is_little_endian: bool,
+  value_colour: F,
+) -> Vec<u8>
+where
+  F: Fn(f64, f64) -> K,
+  K: AsRef<[u32]>,
+{
+  let mut image_data = vec![0u8; values.len() * 4];
+
+  for (idx, (&value, pixel_data)) in values
+    .iter()
+    .zip(image_data.chunks_exact_mut(4))
+    .enumerate()
+  {
+    write_rgba(
+      pixel_data,
+      value_colour(value, bayer_threshold(idx, canvas.width)).as_ref(),
+      is_little_endian,
+    );
+  }
+
+  image_data
+}
+
+fn bayer_threshold(idx: usize, width: u32) -> f64 {
+  let (x, y) = (idx % width.max(1) as usize, idx / width.max(1) as usize);
+
+  (BAYER_4X4[y % 4][x % 4] as f64 + 0.5) / 16.0 - 0.5
+}
+
+/***********************************************************************************************************************
+ * Blend a tint into the pixels that the early bailout checks skip, so that the regions they cover can be seen
+ * The main cardioid is tinted red, the period-2 bulb green and, if they are checked, the period-3 components blue
+ */
+fn tint_bailout_regions(
+  image_data: &mut [u8],
+  canvas: &Dimensions,
+  axes_ranges: &AxesRanges,
+  check_period_3: bool,
+  is_little_endian: bool,
+) {
+  let to_coord = pixel_to_point(canvas, axes_ranges);
+  let channels: [usize; 3] = if is_little_endian {
+    [0, 1, 2]
+  } else {
+    [3, 2, 1]
+  };
+
+  for (idx, pixel_data) in image_data.chunks_exact_mut(4).enumerate() {
+    let loc = to_coord(idx as u32 % canvas.width, idx as u32 / canvas.width);
+    let tint = if is_in_main_cardioid(&loc, sum_of_squares(loc.x - 0.25, loc.y)) {
+      [0xff, 0, 0]
+    } else if is_in_period_2_bulb(&loc) {
+      [0, 0xff, 0]
+    } else if check_period_3 && is_in_period_3_component(&loc) {
+      [0, 0, 0xff]
+    } else {
+      continue;
+    };
+
+    for (&channel, &value) in channels.iter().zip(tint.iter()) {
+      pixel_data[channel] = ((pixel_data[channel] as u32 + value) / 2) as u8;
+    }
+  }
+}
+
+/***********************************************************************************************************************
+ * Mirror RGBA byte data in place, reversing the order of the pixels in each row and/or the order of the rows
+ */
+fn flip_image(image_data: &mut [u8], canvas: &Dimensions, flip_x: bool, flip_y: bool) {
+  let row_len = canvas.width as usize * 4;
+
+  if flip_x {
+    for row in image_data.chunks_exact_mut(row_len) {
+      let width = row.len() / 4;
+
+      for ix in 0..width / 2 {
+        let (left, right) = row.split_at_mut((width - 1 - ix) * 4);
+        left[ix * 4..ix * 4 + 4].swap_with_slice(&mut right[..4]);
+      }
+    }
+  }
+
+  if flip_y {
+    let height = canvas.height as usize;
+
+    for iy in 0..height / 2 {
+      let (top, bottom) = image_data.split_at_mut((height - 1 - iy) * row_len);
+      top[iy * row_len..(iy + 1) * row_len].swap_with_slice(&mut bottom[..row_len]);
+    }
+  }
+}
+
+/***********************************************************************************************************************
+ * Copy RGBA byte data for a region into the RGBA byte data for the whole canvas
+ * The region must lie within the canvas
+ */
+fn copy_region(image_data: &mut [u8], canvas: &Dimensions, region_data: &[u8], region: &Region) {
+  let row_len = region.width as usize * 4;
+
+  for (iy, row) in region_data.chunks_exact(row_len).enumerate() {
+    let start = ((region.y as usize + iy) * canvas.width as usize + region.x as usize) * 4;
+    image_data[start..start + row_len].copy_from_slice(row);
+  }
+}
+
+/***********************************************************************************************************************
+ * Stretch RGBA byte data to different dimensions, copying the nearest source pixel into each destination pixel
+ */
+fn stretch_image(image_data: &[u8], from: &Dimensions, to: &Dimensions) -> Vec<u8> {
+  let mut stretched = Vec::with_capacity((to.width * to.height * 4) as usize);
+
+  for iy in 0..to.height as u64 {
+    let src_y = iy * from.height as u64 / to.height as u64;
+
+    for ix in 0..to.width as u64 {
+      let src_x = ix * from.width as u64 / to.width as u64;
+      let idx = ((src_y * from.width as u64 + src_x) * 4) as usize;
+
+      stretched.extend_from_slice(&image_data[idx..idx + 4]);
+    }
+  }
+
+  stretched
+}
+
+/***********************************************************************************************************************
+ * Return the RGBA byte data for a preview of a colour map, in which each entry is a vertical stripe of equal width
+ * running from the first entry on the left to the last entry on the right
+ */
+fn render_palette_strip(
+  canvas: &Dimensions,
+  colour_map: &[Vec<u32>],
+  is_little_endian: bool,
+) -> Vec<u8> {
+  let width = canvas.width as usize;
+  let row: Vec<u8> = (0..width)
+    .flat_map(|ix| {
+      let this_colour = &colour_map[ix * colour_map.len() / width];
+      let mut pixel_data = [0u8; 4];
+
+      write_rgba(
+        &mut pixel_data,
+        &[this_colour[0], this_colour[1], this_colour[2]],
+        is_little_endian,
+      );
+      pixel_data
+    })
+    .collect();
+
+  row.repeat(canvas.height as usize)
+}
+
+/***********************************************************************************************************************
+ * Write RGBA byte data to the canvas
+ */
+fn put_image(
+  ctx: &CanvasRenderingContext2d,
+  image_data: &[u8],
+  canvas: &Dimensions,
+) -> Result<(), FractalError> {
+  let image_data =
+    ImageData::new_with_u8_clamped_array_and_sh(Clamped(image_data), canvas.width, canvas.height)?;
+  Ok(ctx.put_image_data(&image_data, 0.0, 0.0)?)
+}
+
+/***********************************************************************************************************************
+ * Write RGBA byte data to a rectangular region of the canvas
+ */
+fn put_image_at(
+  ctx: &CanvasRenderingContext2d,
+  image_data: &[u8],
+  region: &Region,
+) -> Result<(), FractalError> {
+  let image_data =
+    ImageData::new_with_u8_clamped_array_and_sh(Clamped(image_data), region.width, region.height)?;
+  Ok(ctx.put_image_data(&image_data, region.x as f64, region.y as f64)?)
+}
+
+/***********************************************************************************************************************
+ * Write the RGBA byte data for a single row of pixels
+ */
+fn draw_row<C, F, K>(
+  row_data: &mut [u8],
+  ix_start: u32,
+  iy: u32,
+  to_coord: &C,
+  pixel_colour: &F,
+  is_little_endian: bool,
+) where
+  C: Fn(u32, u32) -> Point,
+  F: Fn(Point) -> K,
+  K: AsRef<[u32]>,
+{
+  for (ix, pixel_data) in row_data.chunks_exact_mut(4).enumerate() {
+    write_rgba(
+      pixel_data,
+      pixel_colour(to_coord(ix_start + ix as u32, iy)).as_ref(),
+      is_little_endian,
+    );
+  }
+}
+
+/***********************************************************************************************************************
+ * Calculate a value for every pixel on the canvas and return the values in row-major order
+ */
+fn map_pixels<T, F>(canvas: &Dimensions, axes_ranges: &AxesRanges, mut pixel_value: F) -> Vec<T>
+where
+  F: FnMut(Point) -> T,
+{
+  let mut values = Vec::with_capacity((canvas.width * canvas.height) as usize);
+  let to_coord = pixel_to_point(canvas, axes_ranges);
+
+  for iy in 0..canvas.height {
+    for ix in 0..canvas.width {
+      values.push(pixel_value(to_coord(ix, iy)));
+    }
+  }
+
+  values
+}
+
+/***********************************************************************************************************************
+ * Calculate a value for every pixel in the top left quadrant of the canvas, then reflect the quadrant across the
+ * vertical and horizontal centre lines to fill the rest of the canvas
+ * When the width or height is odd, the centre column or row is calculated and lies on the line of reflection
+ */
+fn map_quadrant_pixels<T, F>(
+  canvas: &Dimensions,
+  axes_ranges: &AxesRanges,
+  mut pixel_value: F,
+) -> Vec<T>
+where
+  T: Copy,
+  F: FnMut(Point) -> T,
+{
+  let (q_width, q_height) = (canvas.width.div_ceil(2), canvas.height.div_ceil(2));
+  let to_coord = pixel_to_point(canvas, axes_ranges);
+  let mut quadrant = Vec::with_capacity((q_width * q_height) as usize);
+
+  for iy in 0..q_height {
+    for ix in 0..q_width {
+      quadrant.push(pixel_value(to_coord(ix, iy)));
+    }
+  }
+
+  let mut values = Vec::with_capacity((canvas.width * canvas.height) as usize);
+
+  for iy in 0..canvas.height {
+    let q_y = iy.min(canvas.height - 1 - iy);
+
+    for ix in 0..canvas.width {
+      let q_x = ix.min(canvas.width - 1 - ix);
+      values.push(quadrant[(q_y * q_width + q_x) as usize]);
+    }
+  }
+
+  values
+}
+
+/***********************************************************************************************************************
+ * Build a function that scales an (x,y) canvas location to the fractal's coordinate space
+ */
+fn pixel_to_point(canvas: &Dimensions, axes_ranges: &AxesRanges) -> impl Fn(u32, u32) -> Point {
+  let to_coord = fractional_pixel_to_point(canvas, axes_ranges);
+
+  move |ix: u32, iy: u32| to_coord(ix as f64, iy as f64)
+}
+
+/***********************************************************************************************************************
+ * Build a function that scales a (possibly fractional) canvas location to the fractal's coordinate space
+ */
+fn fractional_pixel_to_point(
+  canvas: &Dimensions,
+  axes_ranges: &AxesRanges,
+) -> impl Fn(f64, f64) -> Point {
+  // Build partial functions to scale (x,y) canvas locations to the fractal's coordinate space
+  let scale = move |scale_factor: f64, length: f64| move |pos: f64| scale_factor * (pos / length);
+  let scale_x = scale(
+    axes_ranges.x_range.max - axes_ranges.x_range.min,
+    (canvas.width.max(2) - 1) as f64,
+  );
+  let scale_y = scale(
+    axes_ranges.y_range.max - axes_ranges.y_range.min,
+    (canvas.height.max(2) - 1) as f64,
+  );
+  let (x_min, y_min) = (axes_ranges.x_range.min, axes_ranges.y_range.min);
+
+  move |px: f64, py: f64| Point {
+    x: x_min + scale_x(px),
+    y: y_min + scale_y(py),
+  }
+}
+
+/***********************************************************************************************************************
+ * Build axes ranges from the given extents, expanding the shorter axis about its centre so that each pixel on the
+ * canvas covers the same distance along both axes
+ */
+fn fit_to_aspect(
+  x_min: f64,
+  x_max: f64,
+  y_min: f64,
+  y_max: f64,
+  canvas: &Dimensions,
+) -> AxesRanges {
+  let x_span = x_max - x_min;
+  let y_span = y_max - y_min;
+  let x_pixels = (canvas.width.max(2) - 1) as f64;
+  let y_pixels = (canvas.height.max(2) - 1) as f64;
+
+  // The distance covered by each pixel is set by whichever axis needs the larger value
+  let pixel_size = (x_span / x_pixels).max(y_span / y_pixels);
+  let x_half = pixel_size * x_pixels / 2.0;
+  let y_half = pixel_size * y_pixels / 2.0;
+  let (x_mid, y_mid) = ((x_min + x_max) / 2.0, (y_min + y_max) / 2.0);
+
+  AxesRanges {
+    x_range: Range {
+      min: x_mid - x_half,
+      max: x_mid + x_half,
+    },
+    y_range: Range {
+      min: y_mid - y_half,
+      max: y_mid + y_half,
+    },
+  }
+}
+
+/***********************************************************************************************************************
+ * Return a function that converts a location in the fractal's coordinate space back to a (possibly fractional) pixel
+ * location on the canvas
+ * This is the inverse of pixel_to_point
+ */
+fn point_to_pixel(
+  canvas: &Dimensions,
+  axes_ranges: &AxesRanges,
+) -> impl Fn(f64, f64) -> (f64, f64) {
+  let x_scale =
+    (canvas.width.max(2) - 1) as f64 / (axes_ranges.x_range.max - axes_ranges.x_range.min);
+  let y_scale =
+    (canvas.height.max(2) - 1) as f64 / (axes_ranges.y_range.max - axes_ranges.y_range.min);
+  let (x_min, y_min) = (axes_ranges.x_range.min, axes_ranges.y_range.min);
+
+  move |x: f64, y: f64| ((x - x_min) * x_scale, (y - y_min) * y_scale)
+}
+
+/***********************************************************************************************************************
+ * Round a grid spacing to the nearest "nice" value of the form 1, 2 or 5 times a power of ten
+ */
+fn nice_spacing(spacing: f64) -> f64 {
+  let magnitude = 10.0_f64.powf(spacing.log10().floor());
+  let nice = match spacing / magnitude {
+    f if f < 1.5 => 1.0,
+    f if f < 3.5 => 2.0,
+    f if f < 7.5 => 5.0,
+    _ => 10.0,
+  };
+
+  nice * magnitude
+}
+
+/***********************************************************************************************************************
+ * Write RGBA byte data for a single pixel according to the processor's endianness
+ * Colours with only 3 channels are fully opaque
+ */
+fn write_rgba(pixel_data: &mut [u8], colour: &[u32], is_little_endian: bool) {
+  let alpha = palette_alpha(colour) as u8;
+
+  if is_little_endian {
+    pixel_data[0] = colour[0] as u8; // Red
+    pixel_data[1] = colour[1] as u8; // Green
+    pixel_data[2] = colour[2] as u8; // Blue
+    pixel_data[3] = alpha;
+  } else {
+    pixel_data[0] = alpha;
+    pixel_data[1] = colour[2] as u8; // Blue
+    pixel_data[2] = colour[1] as u8; // Green
+    pixel_data[3] = colour[0] as u8; // Red
+  }
+}
+
+/***********************************************************************************************************************
+ * Return the colour map index for an iteration value
+ * Iteration values beyond the end of the colour map either wrap around to the start, or are clamped to the last entry.
+ * Points that reach the iteration limit are never wrapped
+ */
+fn palette_index(iters: usize, max_iters: u32, palette_len: usize, wrap_palette: bool) -> usize {
+  if wrap_palette && iters < max_iters as usize {
+    iters % palette_len
+  } else {
+    iters.min(palette_len - 1)
+  }
+}
+
+/***********************************************************************************************************************
+ * Scale an iteration value logarithmically so that the range 0..=max_iters covers the whole colour map
+ * The colour map already spans the full iteration range, so wrapping does not apply to this scale
+ */
+fn log_palette_value(iters: f64, max_iters: u32, palette_len: usize) -> f64 {
+  if max_iters == 0 {
+    0.0
+  } else {
+    iters.max(0.0).ln_1p() / (max_iters as f64).ln_1p() * (palette_len - 1) as f64
+  }
+}
+
+/***********************************************************************************************************************
+ * Count the pixels with each iteration value
+ * The returned vector has max_iters + 1 entries, and the last entry is the number of pixels inside the set
+ */
+fn escape_histogram(iter_buffer: &[usize], max_iters: u32) -> Vec<u32> {
+  let mut histogram = vec![0u32; max_iters as usize + 1];
+
+  for &iters in iter_buffer {
+    histogram[iters.min(max_iters as usize)] += 1;
+  }
+
+  histogram
+}
+
+// The opacity of a colour map entry, which is its optional 4th value
+fn palette_alpha(colour: &[u32]) -> u32 {
+  colour.get(3).map_or(0xff, |&alpha| alpha.min(0xff))
+}
+
+// A colour map entry with its opacity made explicit
+fn palette_rgba(colour: &[u32]) -> [u32; 4] {
+  [colour[0], colour[1], colour[2], palette_alpha(colour)]
+}
+
+/***********************************************************************************************************************
+ * Return the colour for a fractional iteration value by linearly interpolating between adjacent colour map entries
+ */
+fn interpolate_colour(
+  colour_map: &[Vec<u32>],
+  value: f64,
+  max_iters: u32,
+  wrap_palette: bool,
+) -> [u32; 3] {
+  let [red, green, blue, _] = interpolate_rgba(colour_map, value, max_iters, wrap_palette);
+
+  [red, green, blue]
+}
+
+// As interpolate_colour, but the opacity of the colour map entries is interpolated too
+fn interpolate_rgba(
+  colour_map: &[Vec<u32>],
+  value: f64,
+  max_iters: u32,
+  wrap_palette: bool,
+) -> [u32; 4] {
+  let idx = value.floor() as usize;
+  let lower =
+    palette_rgba(&colour_map[palette_index(idx, max_iters, colour_map.len(), wrap_palette)]);
+  let upper =
+    palette_rgba(&colour_map[palette_index(idx + 1, max_iters, colour_map.len(), wrap_palette)]);
+  let fraction = value - value.floor();
+
+  let lerp = |idx: usize| {
+    let (from, to) = (lower[idx] as f64, upper[idx] as f64);
+    (from + (to - from) * fraction).round() as u32
+  };
+
+  [lerp(0), lerp(1), lerp(2), lerp(3)]
+}
+
+/***********************************************************************************************************************
+ * Return the colour for an iteration value after rotating the colour map by a fractional offset
+ * The shifted index wraps around the colour map and is interpolated between adjacent entries.  Points that reach the
+ * iteration limit are not cycled, and an offset of zero gives the same colours as an unshifted colour map
+ */
+fn cycled_colour(
+  colour_map: &[Vec<u32>],
+  iters: usize,
+  max_iters: u32,
+  palette_offset: f64,
+) -> [u32; 3] {
+  let palette_len = colour_map.len();
+  let idx = palette_index(iters, max_iters, palette_len, false);
+
+  if iters >= max_iters as usize {
+    let this_colour = &colour_map[idx];
+    return [this_colour[0], this_colour[1], this_colour[2]];
+  }
+
+  let value = (idx as f64 + palette_offset).rem_euclid(palette_len as f64);
+  let lower = (value.floor() as usize).min(palette_len - 1);
+  let upper = (lower + 1) % palette_len;
+  let fraction = value - lower as f64;
+
+  let lerp = |channel: usize| {
+    let (from, to) = (
+      colour_map[lower][channel] as f64,
+      colour_map[upper][channel] as f64,
+    );
+    (from + (to - from) * fraction).round() as u32
+  };
+
+  [lerp(0), lerp(1), lerp(2)]
+}
+
+/***********************************************************************************************************************
+ * Build a colour map in which each channel of entry i is (sin(freq * i / length + phase) * 0.5 + 0.5) * 255
+ */
+fn sine_colour_map(length: usize, freqs: [f64; 3], phase: f64) -> Vec<Vec<u32>> {
+  (0..length)
+    .map(|idx| {
+      let t = idx as f64 / length as f64;
+
+      freqs
+        .iter()
+        .map(|freq| (((freq * t + phase).sin() * 0.5 + 0.5) * 255.0).round() as u32)
+        .collect()
+    })
+    .collect()
+}
+
+/***********************************************************************************************************************
+ * Stretch or shrink a colour map to the given number of entries by linearly interpolating between adjacent colours
+ * The first and last colours are always preserved exactly
+ */
+fn resample_colour_map(colour_map: &[Vec<u32>], target_len: usize) -> Vec<Vec<u32>> {
+  if target_len == 1 {
+    return vec![colour_map[0][..3].to_vec()];
+  }
+
+  let spacing = (colour_map.len() - 1) as f64 / (target_len.max(2) - 1) as f64;
+
+  (0..target_len)
+    .map(|idx| {
+      let colour = interpolate_colour(colour_map, idx as f64 * spacing, u32::MAX, false);
+      colour.to_vec()
+    })
+    .collect()
+}
+
+/***********************************************************************************************************************
+ * Build a colour map of the given length by linearly interpolating between gradient stops
+ * The stops are sorted by position first.  Positions before the first stop use the first stop's colour, and positions
+ * after the last stop use the last stop's colour
+ */
+fn gradient_colour_map(stops: &mut [GradientStop], length: usize) -> Vec<Vec<u32>> {
+  stops.sort_by(|a, b| {
+    a.pos
+      .partial_cmp(&b.pos)
+      .unwrap_or(std::cmp::Ordering::Equal)
+  });
+
+  let first = &stops[0];
+  let last = &stops[stops.len() - 1];
+
+  (0..length)
+    .map(|idx| {
+      let pos = idx as f64 / (length.max(2) - 1) as f64;
+
+      if pos <= first.pos {
+        return vec![first.r, first.g, first.b];
+      }
+
+      match stops.windows(2).find(|pair| pos <= pair[1].pos) {
+        Some(pair) => {
+          let (from, to) = (&pair[0], &pair[1]);
+          let span = to.pos - from.pos;
+          let fraction = if span > 0.0 {
+            (pos - from.pos) / span
+          } else {
+            1.0
+          };
+          let lerp = |a: u32, b: u32| (a as f64 + (b as f64 - a as f64) * fraction).round() as u32;
+
+          vec![lerp(from.r, to.r), lerp(from.g, to.g), lerp(from.b, to.b)]
+        }
+        None => vec![last.r, last.g, last.b],
+      }
+    })
+    .collect()
+}
+
+/***********************************************************************************************************************
+ * Return the iteration value of a particular pixel for the given fractal type
+ */
+fn fractal_iter(f_type: &FractalType, loc: Point, params: &EscapeParams) -> usize {
+  match *f_type {
+    FractalType::Mandelbrot => mandel_iter(&loc, params),
+    FractalType::Julia(c) => escape_time_mj(&c, loc, params),
+    FractalType::BurningShip => burning_ship_iter(&loc, params),
+    FractalType::Tricorn => tricorn_iter(&loc, params),
+    FractalType::Multibrot(power) => multibrot_iter(&loc, params, power),
+    FractalType::Phoenix(p) => phoenix_iter(&loc, params, &p),
+  }
+}
+
+/***********************************************************************************************************************
+ * Return the fractional iteration value of a particular pixel for the given fractal type
+ * Smooth iteration values are only available for the Mandelbrot and Julia Sets; all other fractal types fall back to
+ * their integer iteration value
+ */
+fn smooth_fractal_iter(f_type: &FractalType, loc: Point, params: &EscapeParams) -> f64 {
+  match *f_type {
+    FractalType::Mandelbrot => smooth_mandel_iter(&loc, params),
+    FractalType::Julia(c) => smooth_escape_time_mj(&c, loc, params),
+    _ => fractal_iter(f_type, loc, params) as f64,
+  }
+}
+
+/***********************************************************************************************************************
+ * Return the iteration value of a particular pixel in the Mandelbrot set
+ * This calculation bails out early if the current point is located within the main cardioid or the period-2 bulb
+ */
+fn mandel_iter(loc: &Point, params: &EscapeParams) -> usize {
+  if mandel_early_bailout_extended(loc, params.check_period_3) {
+    params.max_iters as usize
+  } else {
+    escape_time_mj(loc, Point { x: 0.0, y: 0.0 }, params)
+  }
+}
+
+/***********************************************************************************************************************
+ * Return the fractional iteration value of a particular pixel in the Mandelbrot set
+ */
+fn smooth_mandel_iter(loc: &Point, params: &EscapeParams) -> f64 {
+  if mandel_early_bailout_extended(loc, params.check_period_3) {
+    params.max_iters as f64
+  } else {
+    smooth_escape_time_mj(loc, Point { x: 0.0, y: 0.0 }, params)
+  }
+}
+
+/***********************************************************************************************************************
+ * Return the iteration value of a particular pixel in the Burning Ship fractal
+ * The cardioid and bulb checks only apply to the Mandelbrot Set, so there is no early bailout here
+ */
+fn burning_ship_iter(loc: &Point, params: &EscapeParams) -> usize {
+  escape_time(loc, Point { x: 0.0, y: 0.0 }, params, burning_ship_step)
+}
+
+/***********************************************************************************************************************
+ * Return the iteration value of a particular pixel in the Tricorn (Mandelbar) fractal
+ * As with the Burning Ship, the Mandelbrot cardioid and bulb checks do not apply
+ */
+fn tricorn_iter(loc: &Point, params: &EscapeParams) -> usize {
+  escape_time(loc, Point { x: 0.0, y: 0.0 }, params, tricorn_step)
+}
+
+/***********************************************************************************************************************
+ * Return the iteration value of a particular pixel in the Multibrot set z = z^power + c
+ * A power of 2 is the Mandelbrot Set, so in this case, the early bailout checks can still be used
+ * Powers 3 and 4 are unrolled, and all other powers are calculated by repeated squaring
+ */
+fn multibrot_iter(loc: &Point, params: &EscapeParams, power: u32) -> usize {
+  let origin = Point { x: 0.0, y: 0.0 };
+
+  match power {
+    2 => mandel_iter(loc, params),
+    3 => escape_time(loc, origin, params, |z, c| z.sqr().mul(z).add(c)),
+    4 => escape_time(loc, origin, params, |z, c| z.sqr().sqr().add(c)),
+    _ => escape_time(loc, origin, params, |z, c| z.powi(power).add(c)),
+  }
+}
+
+/***********************************************************************************************************************
+ * Estimate the distance from a particular pixel to the boundary of the Mandelbrot Set
+ * The derivative dz = 2*z*dz + 1 is tracked alongside z, and once the point escapes, the exterior distance estimate is
+ * 2.0 * |z| * ln(|z|) / |dz|.  Points that do not escape are inside the set and have a distance of zero
+ */
+fn mandel_distance_estimate(loc: &Point, max_iters: &u32) -> f64 {
+  if mandel_early_bailout(loc) {
+    return 0.0;
+  }
+
+  let c = Complex::from(*loc);
+  let mut z = Complex::new(0.0, 0.0);
+  let mut dz = Complex::new(0.0, 0.0);
+
+  for _ in 0..*max_iters {
+    if z.abs_sq() > DISTANCE_BAILOUT {
+      let z_mod = z.abs_sq().sqrt();
+      return 2.0 * z_mod * z_mod.ln() / dz.abs_sq().sqrt();
+    }
+
+    dz = Complex::new(2.0, 0.0)
+      .mul(z)
+      .mul(dz)
+      .add(Complex::new(1.0, 0.0));
+    z = mj_step(z, c);
+  }
+
+  0.0
+}
+
+/***********************************************************************************************************************
+ * Return the iteration value of a particular pixel together with the direction of the surface normal at that point
+ * The normal is the unit vector in the direction of u = z/dz, where dz is the same derivative used for the distance
+ * estimate.  Points that do not escape have no normal
+ */
+fn mandel_normal(loc: &Point, max_iters: &u32) -> (usize, Option<Complex>) {
+  if mandel_early_bailout(loc) {
+    return (*max_iters as usize, None);
+  }
+
+  let c = Complex::from(*loc);
+  let mut z = Complex::new(0.0, 0.0);
+  let mut dz = Complex::new(0.0, 0.0);
+
+  for iter_count in 0..*max_iters {
+    if z.abs_sq() > DISTANCE_BAILOUT {
+      let u = z.div(dz);
+      let u_mod = u.abs_sq().sqrt();
+      return (
+        iter_count as usize,
+        Some(Complex::new(u.re / u_mod, u.im / u_mod)),
+      );
+    }
+
+    dz = Complex::new(2.0, 0.0)
+      .mul(z)
+      .mul(dz)
+      .add(Complex::new(1.0, 0.0));
+    z = mj_step(z, c);
+  }
+
+  (*max_iters as usize, None)
+}
+
+/***********************************************************************************************************************
+ * Return the stripe average of a particular pixel: the mean value of 0.5 * sin(stripe_freq * arg(z)) + 0.5 over its
+ * orbit
+ * The result lies between 0 and 1.  Points that do not escape are inside the set and have no stripe average
+ */
+fn mandel_stripe_average(loc: &Point, stripe_freq: f64, max_iters: &u32) -> Option<f64> {
+  if mandel_early_bailout(loc) {
+    return None;
+  }
+
+  let c = Complex::from(*loc);
+  let mut z = Complex::new(0.0, 0.0);
+  let mut total = 0.0;
+
+  for iter_count in 0..*max_iters {
+    z = mj_step(z, c);
+
+    if z.abs_sq() > STRIPE_BAILOUT {
+      return Some(if iter_count == 0 {
+        0.0
+      } else {
+        total / iter_count as f64
+      });
+    }
+
+    total += 0.5 * (stripe_freq * z.im.atan2(z.re)).sin() + 0.5;
+  }
+
+  None
+}
+
+/***********************************************************************************************************************
+ * Return the argument of z at the moment a particular pixel escapes from the Mandelbrot Set, in the range [0, 2pi)
+ * Points that do not escape are inside the set and have no final angle
+ */
+fn mandel_final_angle(loc: &Point, max_iters: &u32) -> Option<f64> {
+  if mandel_early_bailout(loc) {
+    return None;
+  }
+
+  let (iter_count, z) = escape_time_with_z(
+    loc,
+    Point { x: 0.0, y: 0.0 },
+    &EscapeParams::new(*max_iters),
+    mj_step,
+  );
+
+  if iter_count >= *max_iters {
+    None
+  } else {
+    Some(z.im.atan2(z.re).rem_euclid(2.0 * std::f64::consts::PI))
+  }
+}
+
+/***********************************************************************************************************************
+ * Return the Douady-Hubbard potential ln|z_n| / 2^n of a particular pixel in the Mandelbrot Set, where z_n is the first
+ * value in its orbit z_0 = c, z_1 = c^2 + c, ... to pass the bailout
+ * Points that do not escape are inside the set, where the potential is 0
+ */
+fn mandel_potential(loc: &Point, max_iters: &u32) -> f64 {
+  if mandel_early_bailout(loc) {
+    return 0.0;
+  }
+
+  let (iter_count, z) = escape_time_with_z(
+    loc,
+    Point { x: 0.0, y: 0.0 },
+    &EscapeParams {
+      max_iters: *max_iters,
+      bailout: POTENTIAL_BAILOUT,
+      detect_periodicity: false,
+      check_period_3: false,
+    },
+    mj_step,
+  );
+
+  if iter_count >= *max_iters {
+    0.0
+  } else {
+    // The iteration count includes the step from 0 to c, so the escaping value is z_(iter_count - 1)
+    z.abs_sq().ln() / 2.0 / 2f64.powi(iter_count as i32 - 1)
+  }
+}
+
+/***********************************************************************************************************************
+ * Return the iteration value of a particular pixel in the Mandelbrot Set, together with whether the imaginary part of
+ * z was positive when it escaped
+ * Points that do not escape are inside the set and are always reported as not positive
+ */
+fn mandel_binary_decomp(loc: &Point, max_iters: &u32) -> (usize, bool) {
+  if mandel_early_bailout(loc) {
+    return (*max_iters as usize, false);
+  }
+
+  let (iter_count, z) = escape_time_with_z(
+    loc,
+    Point { x: 0.0, y: 0.0 },
+    &EscapeParams::new(*max_iters),
+    mj_step,
+  );
+
+  (iter_count as usize, iter_count < *max_iters && z.im > 0.0)
+}
+
+/***********************************************************************************************************************
+ * Accumulate a Buddhabrot density map
+ * Random values of c are sampled, and for every value that escapes within max_iters iterations, each pixel visited by
+ * its orbit has its hit count incremented.  Values of c that never escape are discarded
+ */
+fn buddhabrot_density(
+  canvas: &Dimensions,
+  axes_ranges: &AxesRanges,
+  samples: u32,
+  max_iters: &u32,
+) -> Vec<u32> {
+  let to_pixel = point_to_pixel(canvas, axes_ranges);
+  let mut density = vec![0u32; (canvas.width * canvas.height) as usize];
+  let mut rng = XorShift64::new(BUDDHABROT_SEED);
+  let mut orbit = Vec::with_capacity(*max_iters as usize);
+
+  for _ in 0..samples {
+    let loc = Point {
+      x: rng.next_in_range(-BUDDHABROT_SAMPLE_RANGE, BUDDHABROT_SAMPLE_RANGE),
+      y: rng.next_in_range(-BUDDHABROT_SAMPLE_RANGE, BUDDHABROT_SAMPLE_RANGE),
+    };
+
+    if mandel_early_bailout(&loc) {
+      continue;
+    }
+
+    let c = Complex::from(loc);
+    let mut z = Complex::new(0.0, 0.0);
+    let mut escaped = false;
+    orbit.clear();
+
+    for _ in 0..*max_iters {
+      z = mj_step(z, c);
+
+      if z.abs_sq() > BAILOUT {
+        escaped = true;
+        break;
+      }
+
+      orbit.push(z);
+    }
+
+    if !escaped {
+      continue;
+    }
+
+    for point in orbit.iter() {
+      let (px, py) = to_pixel(point.re, point.im);
+      let (ix, iy) = (px.round(), py.round());
+
+      if ix >= 0.0 && iy >= 0.0 && ix < canvas.width as f64 && iy < canvas.height as f64 {
+        density[iy as usize * canvas.width as usize + ix as usize] += 1;
+      }
+    }
+  }
+
+  density
+}
+
+/***********************************************************************************************************************
+ * Return the minimum distance between the orbit of a particular pixel and the orbit trap
+ */
+fn mandel_orbit_trap(loc: &Point, trap: &OrbitTrap, max_iters: &u32) -> f64 {
+  let c = Complex::from(*loc);
+  let mut z = Complex::new(0.0, 0.0);
+  let mut min_distance = f64::MAX;
+
+  for _ in 0..*max_iters {
+    z = mj_step(z, c);
+
+    if z.abs_sq() > BAILOUT {
+      break;
+    }
+
+    let distance = match trap {
+      OrbitTrap::Point(point) => sum_of_squares(z.re - point.x, z.im - point.y).sqrt(),
+      OrbitTrap::Cross => z.re.abs().min(z.im.abs()),
+      OrbitTrap::Circle(radius) => (z.abs_sq().sqrt() - radius).abs(),
+    };
+
+    min_distance = min_distance.min(distance);
+  }
+
+  min_distance
+}
+
+/***********************************************************************************************************************
+ * Return the Lyapunov exponent of the logistic map x = r * x * (1 - x), where r alternates between a and b according
+ * to the sequence (true selects a, false selects b)
+ * A negative exponent indicates a stable orbit and a positive exponent indicates chaos
+ */
+fn lyapunov_exponent(loc: &Point, sequence: &[bool], max_iters: &u32) -> f64 {
+  let r_at = |n: u32| {
+    if sequence[n as usize % sequence.len()] {
+      loc.x
+    } else {
+      loc.y
+    }
+  };
+  let mut x = 0.5;
+
+  for n in 0..LYAPUNOV_WARMUP {
+    x = r_at(n) * x * (1.0 - x);
+  }
+
+  let mut total = 0.0;
+
+  for n in LYAPUNOV_WARMUP..LYAPUNOV_WARMUP + *max_iters {
+    let r = r_at(n);
+
+    // ln(0) is -infinity, meaning the orbit is superstable, so there is no point in iterating any further
+    let derivative = (r * (1.0 - 2.0 * x)).abs();
+
+    if derivative == 0.0 {
+      return f64::NEG_INFINITY;
+    }
+
+    total += derivative.ln();
+    x = r * x * (1.0 - x);
+  }
+
+  total / (*max_iters).max(1) as f64
+}
+
+/***********************************************************************************************************************
+ * Parse a Lyapunov sequence such as "AABAB" into a list of flags that are true for A and false for B
+ */
+fn parse_lyapunov_sequence(sequence: &str) -> Result<Vec<bool>, FractalError> {
+  if sequence.is_empty() {
+    return Err(FractalError::InvalidParameter(
+      "Lyapunov sequence has no characters".to_string(),
+    ));
+  }
+
+  sequence
+    .chars()
+    .map(|ch| match ch.to_ascii_uppercase() {
+      'A' => Ok(true),
+      'B' => Ok(false),
+      _ => Err(FractalError::InvalidParameter(format!(
+        "'{}' in the Lyapunov sequence is not A or B",
+        ch
+      ))),
+    })
+    .collect()
+}
+
+/***********************************************************************************************************************
+ * Apply Newton's method for z^3 - 1 to a particular pixel
+ * Returns the index of the root to which the point converges and the number of iterations needed to get there.  If the
+ * point does not converge within max_iters iterations, the returned root index is NEWTON_ROOTS.len()
+ */
+fn newton_iter(loc: &Point, max_iters: &u32) -> (usize, usize) {
+  let (mut x, mut y) = (loc.x, loc.y);
+
+  for iter_count in 0..*max_iters {
+    if let Some(root_idx) = NEWTON_ROOTS
+      .iter()
+      .position(|(root_x, root_y)| sum_of_squares(x - root_x, y - root_y) <= NEWTON_EPSILON)
+    {
+      return (root_idx, iter_count as usize);
+    }
+
+    // z - (z^3 - 1) / 3z^2 simplifies to 2z/3 + 1/(3z^2)
+    let z_sq = Complex::new(x, y).sqr();
+    let denom = 3.0 * z_sq.abs_sq();
+
+    // The derivative is zero at the origin, so there is nowhere for Newton's method to go
+    if denom == 0.0 {
+      break;
+    }
+
+    x = (2.0 * x / 3.0) + (z_sq.re / denom);
+    y = (2.0 * y / 3.0) - (z_sq.im / denom);
+  }
+
+  (NEWTON_ROOTS.len(), *max_iters as usize)
+}
+
+/***********************************************************************************************************************
+ * Apply the Nova iteration z = z - R(z^3 - 1)/3z^2 + c to a particular pixel, where c is the pixel location and R is
+ * the complex relaxation parameter
+ * Iteration starts from the critical point z = 1 and stops when successive values of z converge.  Returns the index of
+ * the root of z^3 - 1 closest to the final value of z and the number of iterations needed to converge.  If the point
+ * does not converge within max_iters iterations, the returned root index is NEWTON_ROOTS.len()
+ * Unless c = 0, z converges to a fixed point of the Nova map rather than to a root of z^3 - 1, so the basin colour only
+ * shows which cube root of unity lies nearest to that fixed point
+ */
+fn nova_iter(loc: &Point, relaxation: &Complex, max_iters: &u32) -> (usize, usize) {
+  let c = Complex::from(*loc);
+  let mut z = Complex::new(1.0, 0.0);
+
+  for iter_count in 0..*max_iters {
+    let z_sq = z.sqr();
+    let denom = Complex::new(3.0, 0.0).mul(z_sq);
+
+    // The derivative is zero at the origin, so there is nowhere for Newton's method to go
+    if denom.abs_sq() == 0.0 {
+      break;
+    }
+
+    let numer = z_sq.mul(z).add(Complex::new(-1.0, 0.0));
+    let step = relaxation.mul(numer.div(denom));
+    let next = z.sub(step).add(c);
+
+    if !next.abs_sq().is_finite() {
+      break;
+    }
+
+    if sum_of_squares(next.re - z.re, next.im - z.im) <= NEWTON_EPSILON {
+      let distance_to = |idx: usize| {
+        let (root_x, root_y) = NEWTON_ROOTS[idx];
+        sum_of_squares(next.re - root_x, next.im - root_y)
+      };
+      let root_idx = (1..NEWTON_ROOTS.len()).fold(0, |nearest, idx| {
+        if distance_to(idx) < distance_to(nearest) {
+          idx
+        } else {
+          nearest
+        }
+      });
+
+      return (root_idx, iter_count as usize);
+    }
+
+    z = next;
+  }
+
+  (NEWTON_ROOTS.len(), *max_iters as usize)
+}
+
+/***********************************************************************************************************************
+ * Return the colour of a point in a Newton-type fractal
+ * The colour map entry for the root is shaded according to the number of iterations needed to converge, and points
+ * that do not converge are black
+ */
+fn newton_colour(
+  colour_map: &[Vec<u32>],
+  root_idx: usize,
+  iters: usize,
+  max_iters: u32,
+) -> [u32; 3] {
+  if root_idx < NEWTON_ROOTS.len() {
+    let shade = 1.0 - (iters as f64 / max_iters as f64);
+    let base = &colour_map[root_idx];
+    let shaded = |idx: usize| (base[idx] as f64 * shade).round() as u32;
+
+    [shaded(0), shaded(1), shaded(2)]
+  } else {
+    [0, 0, 0]
+  }
+}
+
+/***********************************************************************************************************************
+ * Calculate whether the current point lies within the Mandelbrot Set's main cardioid or the period-2 bulb
+ * If it does, then we can bail out early
+ */
+fn mandel_early_bailout(loc: &Point) -> bool {
+  is_in_main_cardioid(loc, sum_of_squares(loc.x - 0.25, loc.y)) || is_in_period_2_bulb(loc)
+}
+
+fn is_in_main_cardioid(loc: &Point, temp: f64) -> bool {
+  temp * (temp + loc.x - 0.25) <= (loc.y * loc.y) / 4.0
+}
+
+fn is_in_period_2_bulb(loc: &Point) -> bool {
+  sum_of_squares(loc.x + 1.0, loc.y) <= 0.0625
+}
+
+/***********************************************************************************************************************
+ * As mandel_early_bailout, but optionally also check the three largest period-3 components
+ * These extra checks only pay for themselves when a large part of the set is visible
+ */
+fn mandel_early_bailout_extended(loc: &Point, check_period_3: bool) -> bool {
+  mandel_early_bailout(loc) || (check_period_3 && is_in_period_3_component(loc))
+}
+
+// Each disk is centred on the nucleus of a period-3 component, and its radius is slightly smaller than the distance
+// from the nucleus to the nearest point on the component's boundary, so every point in the disk lies inside the set
+fn is_in_period_3_component(loc: &Point) -> bool {
+  PERIOD_3_DISKS
+    .iter()
+    .any(|&(x, y, radius_sq)| sum_of_squares(loc.x - x, loc.y - y) <= radius_sq)
+}
+
+/***********************************************************************************************************************
+ * Common escape time algorithm for calculating both the Mandelbrot and Julia Sets
+ */
+fn escape_time_mj(mandel_point: &Point, start_val: Point, params: &EscapeParams) -> usize {
+  escape_time(mandel_point, start_val, params, mj_step)
+}
+
+/***********************************************************************************************************************
+ * Generic escape time algorithm
+ * The iteration rule is passed in as a function that calculates the next value of z from the current value of z and
+ * the point c
+ */
+fn escape_time<F>(mandel_point: &Point, start_val: Point, params: &EscapeParams, step: F) -> usize
+where
+  F: Fn(Complex, Complex) -> Complex,
+{
+  escape_time_with_z(mandel_point, start_val, params, step).0 as usize
+}
+
+/***********************************************************************************************************************
+ * Return the iteration value of a particular pixel in the Phoenix fractal z_{n+1} = z_n^2 + c + p * z_{n-1}
+ * Each step depends on the previous two values of z, so the generic escape time loop cannot be used here.  For the
+ * same reason, periodicity detection is not available
+ */
+fn phoenix_iter(loc: &Point, params: &EscapeParams, p: &Point) -> usize {
+  let c = Complex::from(*loc);
+  let p = Complex::from(*p);
+  let mut z = Complex::new(0.0, 0.0);
+  let mut z_prev = Complex::new(0.0, 0.0);
+  let mut iter_count: u32 = 0;
+
+  while (z.abs_sq() <= params.bailout) && iter_count < params.max_iters {
+    let z_next = z.sqr().add(c).add(p.mul(z_prev));
+    z_prev = z;
+    z = z_next;
+    iter_count += 1;
+  }
+
+  iter_count as usize
+}
+
+/***********************************************************************************************************************
+ * Calculate the orbit of a reference point for perturbation rendering
+ * The orbit starts at zero and stops either when the point escapes or when max_iters iterations have been performed
+ */
+fn reference_orbit(c_ref: Complex, params: &EscapeParams) -> Vec<Complex> {
+  let mut orbit = Vec::with_capacity(params.max_iters as usize + 1);
+  let mut z = Complex::new(0.0, 0.0);
+
+  orbit.push(z);
+
+  while orbit.len() <= params.max_iters as usize {
+    z = mj_step(z, c_ref);
+
+    if z.abs_sq() > params.bailout {
+      break;
+    }
+
+    orbit.push(z);
+  }
+
+  orbit
+}
+
+/***********************************************************************************************************************
+ * Return the iteration value of the point c_ref + dc using perturbation theory
+ * Rather than iterating z directly, this iterates the difference dz between the point's orbit and the reference orbit
+ * using dz = 2 * Z * dz + dz^2 + dc.  Since dz and dc are small, they keep their full precision even when c_ref + dc
+ * cannot be distinguished from c_ref.  Whenever the reference orbit runs out, or the point's orbit gets closer to zero
+ * than dz (which is where precision would otherwise be lost), the orbit is rebased onto the start of the reference
+ * orbit
+ */
+fn perturbation_iter(orbit: &[Complex], dc: Complex, params: &EscapeParams) -> usize {
+  let mut dz = Complex::new(0.0, 0.0);
+  let mut ref_idx = 0;
+
+  for iter_count in 0..params.max_iters {
+    let z = orbit[ref_idx].add(dz);
+
+    if z.abs_sq() > params.bailout {
+      return iter_count as usize;
+    }
+
+    if ref_idx == orbit.len() - 1 || z.abs_sq() < dz.abs_sq() {
+      dz = z;
+      ref_idx = 0;
+    }
+
+    dz = Complex::new(2.0, 0.0)
+      .mul(orbit[ref_idx])
+      .mul(dz)
+      .add(dz.sqr())
+      .add(dc);
+    ref_idx += 1;
+  }
+
+  params.max_iters as usize
+}
+
+/***********************************************************************************************************************
+ * Calculate the iteration value of every pixel in the Mandelbrot Set using Mariani-Silver subdivision
+ * The border of each rectangle is calculated first.  If every border pixel has the same iteration value, the inside of
+ * the rectangle is filled with that value; otherwise the rectangle is split into quarters that share their edges.
+ * Rectangles too small to split are calculated pixel by pixel
+ */
+fn compute_subdivided_buffer(
+  canvas: &Dimensions,      // Canvas dimensions
+  axes_ranges: &AxesRanges, // Extent of X and Y axes
+  params: &EscapeParams,    // Conditions under which the escape time calculation stops
+) -> Vec<usize> {
+  const NOT_CALCULATED: usize = usize::MAX;
+  const MIN_SPLIT_SIZE: u32 = 4;
+
+  let width = canvas.width as usize;
+  let to_coord = pixel_to_point(canvas, axes_ranges);
+  let mut values = vec![NOT_CALCULATED; width * canvas.height as usize];
+  let value_at = |ix: u32, iy: u32, values: &mut [usize]| {
+    let idx = iy as usize * width + ix as usize;
+
+    if values[idx] == NOT_CALCULATED {
+      values[idx] = mandel_iter(&to_coord(ix, iy), params);
+    }
+
+    values[idx]
+  };
+
+  let mut regions = vec![Region {
+    x: 0,
+    y: 0,
+    width: canvas.width,
+    height: canvas.height,
+  }];
+
+  while let Some(region) = regions.pop() {
+    let (x_last, y_last) = (region.x + region.width - 1, region.y + region.height - 1);
+
+    if region.width <= MIN_SPLIT_SIZE || region.height <= MIN_SPLIT_SIZE {
+      for iy in region.y..=y_last {
+        for ix in region.x..=x_last {
+          value_at(ix, iy, &mut values);
+        }
+      }
+
+      continue;
+    }
+
+    let first = value_at(region.x, region.y, &mut values);
+    let mut solid_border = true;
+
+    for ix in region.x..=x_last {
+      solid_border &= value_at(ix, region.y, &mut values) == first;
+      solid_border &= value_at(ix, y_last, &mut values) == first;
+    }
+
+    for iy in region.y..=y_last {
+      solid_border &= value_at(region.x, iy, &mut values) == first;
+      solid_border &= value_at(x_last, iy, &mut values) == first;
+    }
+
+    if solid_border {
+      for iy in region.y + 1..y_last {
+        let row_start = iy as usize * width;
+        values[row_start + region.x as usize + 1..row_start + x_last as usize].fill(first);
+      }
+    } else {
+      let (half_width, half_height) = (region.width / 2, region.height / 2);
+
+      for &(dx, dy) in [
+        (0, 0),
+        (half_width, 0),
+        (0, half_height),
+        (half_width, half_height),
+      ]
+      .iter()
+      {
+        regions.push(Region {
+          x: region.x + dx,
+          y: region.y + dy,
+          width: if dx == 0 {
+            half_width + 1
+          } else {
+            region.width - half_width
+          },
+          height: if dy == 0 {
+            half_height + 1
+          } else {
+            region.height - half_height
+          },
+        });
+      }
+    }
+  }
+
+  values
+}
+
+/***********************************************************************************************************************
+ * Calculate the iteration value of every pixel in the Mandelbrot Set using perturbation theory
+ * The reference point is the centre of the canvas, and each pixel's offset from it is calculated directly from its
+ * distance in pixels, so that the offsets do not suffer from the cancellation of subtracting two nearly equal numbers
+ */
+fn compute_perturbation_buffer(
+  canvas: &Dimensions,      // Canvas dimensions
+  axes_ranges: &AxesRanges, // Extent of X and Y axes
+  params: &EscapeParams,    // Conditions under which the escape time calculation stops
+) -> Vec<usize> {
+  let x_pixels = (canvas.width.max(2) - 1) as f64;
+  let y_pixels = (canvas.height.max(2) - 1) as f64;
+  let pixel_width = (axes_ranges.x_range.max - axes_ranges.x_range.min) / x_pixels;
+  let pixel_height = (axes_ranges.y_range.max - axes_ranges.y_range.min) / y_pixels;
+
+  let c_ref = Complex::new(
+    axes_ranges.x_range.min + pixel_width * x_pixels / 2.0,
+    axes_ranges.y_range.min + pixel_height * y_pixels / 2.0,
+  );
+  let orbit = reference_orbit(c_ref, params);
+
+  // Rebasing needs at least one step of the reference orbit, so if the reference point escapes at once, every pixel is
+  // far enough from the set to be calculated directly
+  if orbit.len() < 2 {
+    return map_pixels(canvas, axes_ranges, |this_coord| {
+      mandel_iter(&this_coord, params)
+    });
+  }
+
+  let mut values = Vec::with_capacity((canvas.width * canvas.height) as usize);
+
+  for iy in 0..canvas.height {
+    for ix in 0..canvas.width {
+      let dc = Complex::new(
+        (ix as f64 - x_pixels / 2.0) * pixel_width,
+        (iy as f64 - y_pixels / 2.0) * pixel_height,
+      );
+      values.push(perturbation_iter(&orbit, dc, params));
+    }
+  }
+
+  values
+}
+
+/***********************************************************************************************************************
+ * Generic escape time algorithm that also returns the final value of z
+ */
+fn escape_time_with_z<F>(
+  mandel_point: &Point,
+  start_val: Point,
+  params: &EscapeParams,
+  step: F,
+) -> (u32, Complex)
+where
+  F: Fn(Complex, Complex) -> Complex,
+{
+  let (iter_count, z, _) = escape_time_with_steps(mandel_point, start_val, params, step);
+  (iter_count, z)
+}
+
+/***********************************************************************************************************************
+ * Generic escape time algorithm that returns the iteration value, the final value of z and the number of steps actually
+ * taken
+ * The step count only differs from the iteration value when a periodic orbit is detected
+ */
+fn escape_time_with_steps<F>(
+  mandel_point: &Point,
+  start_val: Point,
+  params: &EscapeParams,
+  step: F,
+) -> (u32, Complex, u32)
+where
+  F: Fn(Complex, Complex) -> Complex,
+{
+  let c = Complex::from(*mandel_point);
+  let mut z = Complex::from(start_val);
+  let mut iter_count: u32 = 0;
+
+  // Brent's cycle detection: compare z against a saved value that is replaced each time the cycle length being tested
+  // reaches a power of two
+  let mut saved = z;
+  let mut cycle_len: u32 = 0;
+  let mut cycle_limit: u32 = 1;
+
+  // Count the number of iterations needed before the value at the current location either escapes to infinity or hits
+  // the iteration limit
+  while (z.abs_sq() <= params.bailout) && iter_count < params.max_iters {
+    z = step(z, c);
+    iter_count += 1;
+
+    if params.detect_periodicity {
+      // A periodic orbit never escapes, so the point is in the set
+      if sum_of_squares(z.re - saved.re, z.im - saved.im) < PERIODICITY_EPSILON {
+        return (params.max_iters, z, iter_count);
+      }
+
+      cycle_len += 1;
+
+      if cycle_len == cycle_limit {
+        saved = z;
+        cycle_len = 0;
+        cycle_limit *= 2;
+      }
+    }
+  }
+
+  (iter_count, z, iter_count)
+}
+
+/***********************************************************************************************************************
+ * Smooth (continuous) escape time algorithm for calculating both the Mandelbrot and Julia Sets
+ * Points that escape return the fractional value n + 1 - log2(log2(|z|)), and points that reach the iteration limit
+ * return max_iters
+ */
+fn smooth_escape_time_mj(mandel_point: &Point, start_val: Point, params: &EscapeParams) -> f64 {
+  let (iter_count, z) = escape_time_with_z(mandel_point, start_val, params, mj_step);
+
+  if iter_count >= params.max_iters {
+    params.max_iters as f64
+  } else {
+    let log_modulus = z.abs_sq().ln() / 2.0;
+    let smooth_val = iter_count as f64 + 1.0 - (log_modulus / std::f64::consts::LN_2).log2();
+
+    smooth_val.max(0.0).min(params.max_iters as f64)
+  }
+}
+
+/***********************************************************************************************************************
+ * Iteration rules
+ */
+
+// z = z^2 + c
+fn mj_step(z: Complex, c: Complex) -> Complex {
+  z.sqr().add(c)
+}
+
+// z = (|Re z| + i|Im z|)^2 + c
+fn burning_ship_step(z: Complex, c: Complex) -> Complex {
+  Complex::new(z.re.abs(), z.im.abs()).sqr().add(c)
+}
+
+// z = conj(z)^2 + c
+fn tricorn_step(z: Complex, c: Complex) -> Complex {
+  z.conj().sqr().add(c)
+}
+
+/***********************************************************************************************************************
+ * Utility functions
+ */
+fn sum_of_squares(val1: f64, val2: f64) -> f64 {
+  val1 * val1 + val2 * val2
+}
+
+// Use the supplied bailout value if it is at least the default value, else fall back to the default
+fn valid_bailout(bailout: Option<f64>) -> f64 {
+  match bailout {
+    Some(val) if val >= BAILOUT => val,
+    _ => BAILOUT,
+  }
+}
+
+// *********************************************************************************************************************
+// PUBLIC API
+// *********************************************************************************************************************
+
+#[wasm_bindgen]
+extern "C" {
+  #[wasm_bindgen(js_namespace = console)]
+  fn log(s: String);
+}
+
+/***********************************************************************************************************************
+ * Dummy entry point
+ */
+#[wasm_bindgen(start)]
+pub fn main() -> Result<(), FractalError> {
+  log("WASM initialising...".to_string());
+  Ok(())
+}
+
+/***********************************************************************************************************************
+ * Is the processor little endian?
+ * Call this once at startup and pass the result as the is_little_endian argument of the draw functions
+ */
+#[wasm_bindgen]
+pub fn detect_endianness() -> bool {
+  cfg!(target_endian = "little")
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set
+ * If the colour map does not change between renders, draw_mandel_rgb is faster.  A max_iters of 0 draws the whole
+ * canvas in the first colour map entry
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_mandel(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,               // Canvas dimensions
+  axes_ranges: AxesRanges,          // Extent of axes ranges
+  max_iters: u32,                   // Stop after this many iterations
+  c_map: JsValue,                   // Selected colour map
+  is_little_endian: bool,           // Is the processor little endian?
+  bailout: Option<f64>,             // Escape when |z|^2 exceeds this value (default 4.0)
+  wrap_palette: Option<bool>,       // Repeat the colour map instead of clamping? (default false)
+  detect_periodicity: Option<bool>, // Stop early on periodic orbits? (default false)
+  use_symmetry: Option<bool>,       // Mirror the image about the x axis? (default false)
+  preserve_aspect: Option<bool>,    // Keep the canvas aspect ratio? (default false)
+  color_scale: Option<ColorScale>,  // Iteration to colour mapping (default Linear)
+  render_mask: Option<RenderMask>,  // Pixels to draw; others are transparent (default All)
+  alpha: Option<u8>,                // Opacity of the pixels drawn (default 255)
+  interior_color: Option<Vec<u8>>, // RGB colour of points inside the set (default last colour map entry)
+) -> Result<(), FractalError> {
+  draw_fractal(
+    ctx,
+    canvas,
+    axes_ranges,
+    EscapeParams {
+      max_iters,
+      bailout: valid_bailout(bailout),
+      detect_periodicity: detect_periodicity.unwrap_or(false),
+      check_period_3: false,
+    },
+    c_map,
+    FractalType::Mandelbrot,
+    RenderOptions {
+      wrap_palette: wrap_palette.unwrap_or(false),
+      use_symmetry: use_symmetry.unwrap_or(false),
+      preserve_aspect: preserve_aspect.unwrap_or(false),
+      color_scale: color_scale.unwrap_or(ColorScale::Linear),
+      mask: render_mask.unwrap_or(RenderMask::All),
+      alpha: alpha.unwrap_or(0xff),
+      interior_colour: parse_interior_colour(interior_color)?,
+      ..RenderOptions::new(is_little_endian)
+    },
+  )
+}
+
+/***********************************************************************************************************************
+ * Draw a Julia Set
+ * If the colour map does not change between renders, draw_julia_rgb is faster
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_julia(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,               // Canvas dimensions
+  axes_ranges: AxesRanges,          // Extent of axes ranges
+  mouse_loc: Point,                 // Mouse pointer coords on Mandelbrot set
+  max_iters: u32,                   // Stop after this many iterations
+  c_map: JsValue,                   // Selected colour map
+  is_little_endian: bool,           // Is the processor little endian?
+  bailout: Option<f64>,             // Escape when |z|^2 exceeds this value (default 4.0)
+  wrap_palette: Option<bool>,       // Repeat the colour map instead of clamping? (default false)
+  detect_periodicity: Option<bool>, // Stop early on periodic orbits? (default false)
+  preserve_aspect: Option<bool>,    // Keep the canvas aspect ratio? (default false)
+  color_scale: Option<ColorScale>,  // Iteration to colour mapping (default Linear)
+  render_mask: Option<RenderMask>,  // Pixels to draw; others are transparent (default All)
+  alpha: Option<u8>,                // Opacity of the pixels drawn (default 255)
+  interior_color: Option<Vec<u8>>, // RGB colour of points inside the set (default last colour map entry)
+) -> Result<(), FractalError> {
+  draw_fractal(
+    ctx,
+    canvas,
+    axes_ranges,
+    EscapeParams {
+      max_iters,
+      bailout: valid_bailout(bailout),
+      detect_periodicity: detect_periodicity.unwrap_or(false),
+      check_period_3: false,
+    },
+    c_map,
+    FractalType::Julia(mouse_loc),
+    RenderOptions {
+      wrap_palette: wrap_palette.unwrap_or(false),
+      preserve_aspect: preserve_aspect.unwrap_or(false),
+      color_scale: color_scale.unwrap_or(ColorScale::Linear),
+      mask: render_mask.unwrap_or(RenderMask::All),
+      alpha: alpha.unwrap_or(0xff),
+      interior_colour: parse_interior_colour(interior_color)?,
+      ..RenderOptions::new(is_little_endian)
+    },
+  )
+}
+
+/***********************************************************************************************************************
+ * Draw a Julia Set for a value of c passed as a complex number object {re, im}
+ * All other options take the same defaults as draw_julia
+ */
+#[wasm_bindgen]
+pub fn draw_julia_c(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  c: JsValue,              // Parameter of the Julia Set as {re, im}
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  let c: Complex = from_js_value(
+    &c,
+    FractalError::InvalidParameter("expected c as {re, im}".to_string()),
+  )?;
+
+  draw_julia(
+    ctx,
+    canvas,
+    axes_ranges,
+    Point { x: c.re, y: c.im },
+    max_iters,
+    c_map,
+    is_little_endian,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+  )
+}
+
+/***********************************************************************************************************************
+ * Draw the Burning Ship fractal
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_burning_ship(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,               // Canvas dimensions
+  axes_ranges: AxesRanges,          // Extent of axes ranges
+  max_iters: u32,                   // Stop after this many iterations
+  c_map: JsValue,                   // Selected colour map
+  is_little_endian: bool,           // Is the processor little endian?
+  bailout: Option<f64>,             // Escape when |z|^2 exceeds this value (default 4.0)
+  wrap_palette: Option<bool>,       // Repeat the colour map instead of clamping? (default false)
+  detect_periodicity: Option<bool>, // Stop early on periodic orbits? (default false)
+  preserve_aspect: Option<bool>,    // Keep the canvas aspect ratio? (default false)
+  color_scale: Option<ColorScale>,  // Iteration to colour mapping (default Linear)
+  render_mask: Option<RenderMask>,  // Pixels to draw; others are transparent (default All)
+  alpha: Option<u8>,                // Opacity of the pixels drawn (default 255)
+  interior_color: Option<Vec<u8>>, // RGB colour of points inside the set (default last colour map entry)
+) -> Result<(), FractalError> {
+  draw_fractal(
+    ctx,
+    canvas,
+    axes_ranges,
+    EscapeParams {
+      max_iters,
+      bailout: valid_bailout(bailout),
+      detect_periodicity: detect_periodicity.unwrap_or(false),
+      check_period_3: false,
+    },
+    c_map,
+    FractalType::BurningShip,
+    RenderOptions {
+      wrap_palette: wrap_palette.unwrap_or(false),
+      preserve_aspect: preserve_aspect.unwrap_or(false),
+      color_scale: color_scale.unwrap_or(ColorScale::Linear),
+      mask: render_mask.unwrap_or(RenderMask::All),
+      alpha: alpha.unwrap_or(0xff),
+      interior_colour: parse_interior_colour(interior_color)?,
+      ..RenderOptions::new(is_little_endian)
+    },
+  )
+}
+
+/***********************************************************************************************************************
+ * Draw the Tricorn (Mandelbar) fractal
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_tricorn(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,               // Canvas dimensions
+  axes_ranges: AxesRanges,          // Extent of axes ranges
+  max_iters: u32,                   // Stop after this many iterations
+  c_map: JsValue,                   // Selected colour map
+  is_little_endian: bool,           // Is the processor little endian?
+  bailout: Option<f64>,             // Escape when |z|^2 exceeds this value (default 4.0)
+  wrap_palette: Option<bool>,       // Repeat the colour map instead of clamping? (default false)
+  detect_periodicity: Option<bool>, // Stop early on periodic orbits? (default false)
+  preserve_aspect: Option<bool>,    // Keep the canvas aspect ratio? (default false)
+  color_scale: Option<ColorScale>,  // Iteration to colour mapping (default Linear)
+  render_mask: Option<RenderMask>,  // Pixels to draw; others are transparent (default All)
+  alpha: Option<u8>,                // Opacity of the pixels drawn (default 255)
+  interior_color: Option<Vec<u8>>, // RGB colour of points inside the set (default last colour map entry)
+) -> Result<(), FractalError> {
+  draw_fractal(
+    ctx,
+    canvas,
+    axes_ranges,
+    EscapeParams {
+      max_iters,
+      bailout: valid_bailout(bailout),
+      detect_periodicity: detect_periodicity.unwrap_or(false),
+      check_period_3: false,
+    },
+    c_map,
+    FractalType::Tricorn,
+    RenderOptions {
+      wrap_palette: wrap_palette.unwrap_or(false),
+      preserve_aspect: preserve_aspect.unwrap_or(false),
+      color_scale: color_scale.unwrap_or(ColorScale::Linear),
+      mask: render_mask.unwrap_or(RenderMask::All),
+      alpha: alpha.unwrap_or(0xff),
+      interior_colour: parse_interior_colour(interior_color)?,
+      ..RenderOptions::new(is_little_endian)
+    },
+  )
+}
+
+/***********************************************************************************************************************
+ * Draw a Multibrot Set z = z^power + c
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_multibrot(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,               // Canvas dimensions
+  axes_ranges: AxesRanges,          // Extent of axes ranges
+  max_iters: u32,                   // Stop after this many iterations
+  c_map: JsValue,                   // Selected colour map
+  is_little_endian: bool,           // Is the processor little endian?
+  power: u32,                       // Exponent applied to z
+  bailout: Option<f64>,             // Escape when |z|^2 exceeds this value (default 4.0)
+  wrap_palette: Option<bool>,       // Repeat the colour map instead of clamping? (default false)
+  detect_periodicity: Option<bool>, // Stop early on periodic orbits? (default false)
+  preserve_aspect: Option<bool>,    // Keep the canvas aspect ratio? (default false)
+  color_scale: Option<ColorScale>,  // Iteration to colour mapping (default Linear)
+  render_mask: Option<RenderMask>,  // Pixels to draw; others are transparent (default All)
+  alpha: Option<u8>,                // Opacity of the pixels drawn (default 255)
+  interior_color: Option<Vec<u8>>, // RGB colour of points inside the set (default last colour map entry)
+) -> Result<(), FractalError> {
+  draw_fractal(
+    ctx,
+    canvas,
+    axes_ranges,
+    EscapeParams {
+      max_iters,
+      bailout: valid_bailout(bailout),
+      detect_periodicity: detect_periodicity.unwrap_or(false),
+      check_period_3: false,
+    },
+    c_map,
+    FractalType::Multibrot(power),
+    RenderOptions {
+      wrap_palette: wrap_palette.unwrap_or(false),
+      preserve_aspect: preserve_aspect.unwrap_or(false),
+      color_scale: color_scale.unwrap_or(ColorScale::Linear),
+      mask: render_mask.unwrap_or(RenderMask::All),
+      alpha: alpha.unwrap_or(0xff),
+      interior_colour: parse_interior_colour(interior_color)?,
+      ..RenderOptions::new(is_little_endian)
+    },
+  )
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set, optionally using smooth colouring to remove the banding between iteration values
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_mandel_smooth(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,               // Canvas dimensions
+  axes_ranges: AxesRanges,          // Extent of axes ranges
+  max_iters: u32,                   // Stop after this many iterations
+  c_map: JsValue,                   // Selected colour map
+  is_little_endian: bool,           // Is the processor little endian?
+  smooth: bool,                     // Interpolate between colour map entries?
+  bailout: Option<f64>,             // Escape when |z|^2 exceeds this value (default 4.0)
+  wrap_palette: Option<bool>,       // Repeat the colour map instead of clamping? (default false)
+  detect_periodicity: Option<bool>, // Stop early on periodic orbits? (default false)
+  preserve_aspect: Option<bool>,    // Keep the canvas aspect ratio? (default false)
+  color_scale: Option<ColorScale>,  // Iteration to colour mapping (default Linear)
+  render_mask: Option<RenderMask>,  // Pixels to draw; others are transparent (default All)
+  alpha: Option<u8>,                // Opacity of the pixels drawn (default 255)
+  interior_color: Option<Vec<u8>>, // RGB colour of points inside the set (default last colour map entry)
+) -> Result<(), FractalError> {
+  draw_fractal(
+    ctx,
+    canvas,
+    axes_ranges,
+    EscapeParams {
+      max_iters,
+      bailout: valid_bailout(bailout),
+      detect_periodicity: detect_periodicity.unwrap_or(false),
+      check_period_3: false,
+    },
+    c_map,
+    FractalType::Mandelbrot,
+    RenderOptions {
+      smooth,
+      wrap_palette: wrap_palette.unwrap_or(false),
+      preserve_aspect: preserve_aspect.unwrap_or(false),
+      color_scale: color_scale.unwrap_or(ColorScale::Linear),
+      mask: render_mask.unwrap_or(RenderMask::All),
+      alpha: alpha.unwrap_or(0xff),
+      interior_colour: parse_interior_colour(interior_color)?,
+      ..RenderOptions::new(is_little_endian)
+    },
+  )
+}
+
+/***********************************************************************************************************************
+ * Draw a Julia Set, optionally using smooth colouring to remove the banding between iteration values
+ * The fractional iteration values are calculated in the same way as for draw_mandel_smooth
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_julia_smooth(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,               // Canvas dimensions
+  axes_ranges: AxesRanges,          // Extent of axes ranges
+  mouse_loc: Point,                 // Mouse pointer coords on Mandelbrot set
+  max_iters: u32,                   // Stop after this many iterations
+  c_map: JsValue,                   // Selected colour map
+  is_little_endian: bool,           // Is the processor little endian?
+  smooth: bool,                     // Interpolate between colour map entries?
+  bailout: Option<f64>,             // Escape when |z|^2 exceeds this value (default 4.0)
+  wrap_palette: Option<bool>,       // Repeat the colour map instead of clamping? (default false)
+  detect_periodicity: Option<bool>, // Stop early on periodic orbits? (default false)
+  preserve_aspect: Option<bool>,    // Keep the canvas aspect ratio? (default false)
+  color_scale: Option<ColorScale>,  // Iteration to colour mapping (default Linear)
+  render_mask: Option<RenderMask>,  // Pixels to draw; others are transparent (default All)
+  alpha: Option<u8>,                // Opacity of the pixels drawn (default 255)
+  interior_color: Option<Vec<u8>>, // RGB colour of points inside the set (default last colour map entry)
+) -> Result<(), FractalError> {
+  draw_fractal(
+    ctx,
+    canvas,
+    axes_ranges,
+    EscapeParams {
+      max_iters,
+      bailout: valid_bailout(bailout),
+      detect_periodicity: detect_periodicity.unwrap_or(false),
+      check_period_3: false,
+    },
+    c_map,
+    FractalType::Julia(mouse_loc),
+    RenderOptions {
+      smooth,
+      wrap_palette: wrap_palette.unwrap_or(false),
+      preserve_aspect: preserve_aspect.unwrap_or(false),
+      color_scale: color_scale.unwrap_or(ColorScale::Linear),
+      mask: render_mask.unwrap_or(RenderMask::All),
+      alpha: alpha.unwrap_or(0xff),
+      interior_colour: parse_interior_colour(interior_color)?,
+      ..RenderOptions::new(is_little_endian)
+    },
+  )
+}
+
+/***********************************************************************************************************************
+ * Calculate the Mandelbrot Set without drawing it
+ * Returns one iteration count per pixel in row-major order
+ */
+#[wasm_bindgen]
+pub fn compute_mandel_iters(
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+) -> Result<Vec<u32>, FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let iter_buffer = compute_fractal_buffer(
+    &canvas,
+    &axes_ranges,
+    &EscapeParams::new(max_iters),
+    &FractalType::Mandelbrot,
+  );
+
+  Ok(iter_buffer.into_iter().map(|iters| iters as u32).collect())
+}
+
+/***********************************************************************************************************************
+ * Calculate the Mandelbrot Set without drawing it, returning the iteration counts as 16-bit values
+ * This halves the size of the buffer returned by compute_mandel_iters, which suits R16UI textures.  Iteration counts
+ * above 65535 are clamped, so interior points have a value of min(max_iters, 65535)
+ */
+#[wasm_bindgen]
+pub fn compute_mandel_iters_u16(
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+) -> Result<Vec<u16>, FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let iter_buffer = compute_fractal_buffer(
+    &canvas,
+    &axes_ranges,
+    &EscapeParams::new(max_iters),
+    &FractalType::Mandelbrot,
+  );
+
+  Ok(
+    iter_buffer
+      .into_iter()
+      .map(|iters| iters.min(u16::MAX as usize) as u16)
+      .collect(),
+  )
+}
+
+/***********************************************************************************************************************
+ * Calculate the fractional iteration values of the Mandelbrot Set without drawing it
+ * Returns one value per pixel in row-major order, suitable for uploading as a floating point texture.  Interior points
+ * have a value of exactly max_iters
+ */
+#[wasm_bindgen]
+pub fn compute_mandel_smooth_f32(
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+) -> Result<Vec<f32>, FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let params = EscapeParams::new(max_iters);
+
+  Ok(map_pixels(&canvas, &axes_ranges, |this_coord| {
+    smooth_mandel_iter(&this_coord, &params) as f32
+  }))
+}
+
+/***********************************************************************************************************************
+ * Rendering options shared by any number of draws
+ * Each setter consumes the configuration and returns the updated copy, so the calls can be chained:
+ *   new RenderConfig().with_bailout(16.0).with_smoothing(true)
+ */
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct RenderConfig {
+  bailout: f64,                      // Escape when |z|^2 exceeds this value
+  power: u32,                        // Exponent applied to z
+  alpha: u8,                         // Opacity of the pixels drawn
+  color_scale: ColorScale,           // Iteration to colour mapping
+  smooth: bool,                      // Interpolate between colour map entries?
+  interior_colour: Option<[u32; 3]>, // Colour of points inside the set
+  flip_x: bool,                      // Mirror the image left to right?
+  flip_y: bool,                      // Mirror the image top to bottom?
+  check_period_3: bool,              // Skip points in the largest period-3 components?
+  dither: bool,                      // Dither between adjacent colour map entries?
+  refine_boundary: bool,             // Recalculate pixels on the edge of the set?
+  show_bailout_regions: bool,        // Tint the points skipped by early bailout?
+}
+
+impl Default for RenderConfig {
+  fn default() -> Self {
+    RenderConfig::new()
+  }
+}
+
+#[wasm_bindgen]
+impl RenderConfig {
+  #[wasm_bindgen(constructor)]
+  pub fn new() -> RenderConfig {
+    RenderConfig {
+      bailout: BAILOUT,
+      power: 2,
+      alpha: 0xff,
+      color_scale: ColorScale::Linear,
+      smooth: false,
+      interior_colour: None,
+      flip_x: false,
+      flip_y: false,
+      check_period_3: false,
+      dither: false,
+      refine_boundary: false,
+      show_bailout_regions: false,
+    }
+  }
+
+  // Values below the default bailout of 4.0 are replaced by the default
+  pub fn with_bailout(mut self, bailout: f64) -> RenderConfig {
+    self.bailout = valid_bailout(Some(bailout));
+    self
+  }
+
+  // A power of 2 draws the Mandelbrot Set, and any other power draws the corresponding Multibrot Set
+  pub fn with_power(mut self, power: u32) -> RenderConfig {
+    self.power = power;
+    self
+  }
+
+  pub fn with_alpha(mut self, alpha: u8) -> RenderConfig {
+    self.alpha = alpha;
+    self
+  }
+
+  pub fn with_color_scale(mut self, color_scale: ColorScale) -> RenderConfig {
+    self.color_scale = color_scale;
+    self
+  }
+
+  // Smooth colouring is only available for a power of 2
+  pub fn with_smoothing(mut self, smooth: bool) -> RenderConfig {
+    self.smooth = smooth;
+    self
+  }
+
+  // The interior colour must hold exactly the red, green and blue values
+  pub fn with_interior_color(
+    mut self,
+    interior_color: Vec<u8>,
+  ) -> Result<RenderConfig, FractalError> {
+    self.interior_colour = parse_interior_colour(Some(interior_color))?;
+    Ok(self)
+  }
+
+  // Checking the period-3 components speeds up views of the whole set, but slows down zoomed in views
+  pub fn with_period_3_check(mut self, check_period_3: bool) -> RenderConfig {
+    self.check_period_3 = check_period_3;
+    self
+  }
+
+  pub fn with_dither(mut self, dither: bool) -> RenderConfig {
+    self.dither = dither;
+    self
+  }
+
+  pub fn with_refine_boundary(mut self, refine_boundary: bool) -> RenderConfig {
+    self.refine_boundary = refine_boundary;
+    self
+  }
+
+  // The tint is only added to the Mandelbrot Set, since the early bailout checks are not used for other powers
+  pub fn with_bailout_regions(mut self, show_bailout_regions: bool) -> RenderConfig {
+    self.show_bailout_regions = show_bailout_regions;
+    self
+  }
+
+  // Flipping happens after the image has been calculated, so it does not change which part of the fractal is drawn
+  pub fn with_flip(mut self, flip_x: bool, flip_y: bool) -> RenderConfig {
+    self.flip_x = flip_x;
+    self.flip_y = flip_y;
+    self
+  }
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot (or Multibrot) Set using the options held in a RenderConfig
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_cfg(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+  config: &RenderConfig,   // Rendering options
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let f_type = match config.power {
+    2 => FractalType::Mandelbrot,
+    power => FractalType::Multibrot(power),
+  };
+
+  let mut image_data = render_fractal(
+    &canvas,
+    &axes_ranges,
+    EscapeParams {
+      max_iters,
+      bailout: config.bailout,
+      detect_periodicity: false,
+      check_period_3: config.check_period_3,
+    },
+    &colour_map,
+    f_type,
+    &RenderOptions {
+      smooth: config.smooth,
+      color_scale: config.color_scale,
+      alpha: config.alpha,
+      interior_colour: config.interior_colour,
+      refine_boundary: config.refine_boundary,
+      dither: config.dither,
+      ..RenderOptions::new(is_little_endian)
+    },
+  );
+
+  if config.show_bailout_regions && config.power == 2 {
+    tint_bailout_regions(
+      &mut image_data,
+      &canvas,
+      &axes_ranges,
+      config.check_period_3,
+      is_little_endian,
+    );
+  }
+
+  flip_image(&mut image_data, &canvas, config.flip_x, config.flip_y);
+  put_image(ctx, &image_data, &canvas)
+}
+
+/***********************************************************************************************************************
+ * Calculate the Mandelbrot Set without drawing it and return the number of pixels that escaped at each iteration
+ * Entry i holds the number of pixels that escaped after exactly i iterations, and the last entry (at index max_iters)
+ * holds the number of pixels inside the set
+ */
+#[wasm_bindgen]
+pub fn iteration_histogram(
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+) -> Result<Vec<u32>, FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let iter_buffer = compute_fractal_buffer(
+    &canvas,
+    &axes_ranges,
+    &EscapeParams::new(max_iters),
+    &FractalType::Mandelbrot,
+  );
+
+  Ok(escape_histogram(&iter_buffer, max_iters))
+}
+
+/***********************************************************************************************************************
+ * Mandelbrot Set renderer for smooth panning
+ * The iteration values of the last render are kept, so that when the view is dragged by a whole number of pixels, only
+ * the strip of newly exposed pixels needs to be calculated
+ */
+#[wasm_bindgen]
+pub struct CachedRenderer {
+  canvas: Dimensions,
+  max_iters: u32,
+  colour_map: Vec<Vec<u32>>,
+  is_little_endian: bool,
+  last_render: Option<(AxesRanges, Vec<usize>)>,
+}
+
+#[wasm_bindgen]
+impl CachedRenderer {
+  #[wasm_bindgen(constructor)]
+  pub fn new(
+    canvas: Dimensions,     // Canvas dimensions
+    max_iters: u32,         // Stop after this many iterations
+    c_map: JsValue,         // Selected colour map
+    is_little_endian: bool, // Is the processor little endian?
+  ) -> Result<CachedRenderer, FractalError> {
+    validate_dimensions(&canvas)?;
+
+    Ok(CachedRenderer {
+      canvas,
+      max_iters,
+      colour_map: parse_colour_map(&c_map)?,
+      is_little_endian,
+      last_render: None,
+    })
+  }
+
+  // Draw the Mandelbrot Set for the given axes ranges, reusing as much of the previous render as possible
+  pub fn render(
+    &mut self,
+    ctx: &CanvasRenderingContext2d,
+    axes_ranges: AxesRanges,
+  ) -> Result<(), FractalError> {
+    let params = EscapeParams::new(self.max_iters);
+    let pixel_value = |this_coord| fractal_iter(&FractalType::Mandelbrot, this_coord, &params);
+
+    let iter_buffer = match &self.last_render {
+      Some((old_axes, old_buffer)) => shift_iter_buffer(
+        &self.canvas,
+        old_axes,
+        old_buffer,
+        &axes_ranges,
+        pixel_value,
+      ),
+      None => map_pixels(&self.canvas, &axes_ranges, pixel_value),
+    };
+
+    self.last_render = Some((axes_ranges, iter_buffer));
+    self.redraw(ctx, 0.0)
+  }
+
+  // Redraw the last render with the colour map rotated by palette_offset entries, without recalculating any pixels
+  // Incrementing the offset on each animation frame makes the colours flow through the image
+  pub fn redraw(
+    &self,
+    ctx: &CanvasRenderingContext2d,
+    palette_offset: f64,
+  ) -> Result<(), FractalError> {
+    let iter_buffer = match &self.last_render {
+      Some((_, iter_buffer)) => iter_buffer,
+      None => {
+        return Err(FractalError::InvalidParameter(
+          "nothing has been rendered yet".to_string(),
+        ))
+      }
+    };
+
+    let image_data = render_buffer(iter_buffer, self.is_little_endian, |&iters| {
+      cycled_colour(&self.colour_map, iters, self.max_iters, palette_offset)
+    });
+
+    put_image(ctx, &image_data, &self.canvas)
+  }
+}
+
+/***********************************************************************************************************************
+ * Cancellable Mandelbrot Set renderer
+ * The cancellation flag is checked before each row is calculated.  If it has been set, the rows calculated so far are
+ * drawn and the rest of the canvas is left transparent.  On the browser's main thread, JavaScript can only call cancel
+ * between renders, so to interrupt a render in progress, the render must run in a worker that shares its memory with
+ * the caller
+ */
+#[wasm_bindgen]
+pub struct RenderHandle {
+  cancelled: Arc<AtomicBool>,
+}
+
+impl Default for RenderHandle {
+  fn default() -> Self {
+    RenderHandle::new()
+  }
+}
+
+#[wasm_bindgen]
+impl RenderHandle {
+  #[wasm_bindgen(constructor)]
+  pub fn new() -> RenderHandle {
+    RenderHandle {
+      cancelled: Arc::new(AtomicBool::new(false)),
+    }
+  }
+
+  // Stop the current render as soon as the row being calculated is finished
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::Relaxed);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::Relaxed)
+  }
+
+  // Clear the cancellation flag so that the handle can be used for another render
+  pub fn reset(&self) {
+    self.cancelled.store(false, Ordering::Relaxed);
+  }
+
+  // Draw the Mandelbrot Set, returning true if the render completed or false if it was cancelled
+  pub fn draw_mandel(
+    &self,
+    ctx: &CanvasRenderingContext2d,
+    canvas: Dimensions,      // Canvas dimensions
+    axes_ranges: AxesRanges, // Extent of axes ranges
+    max_iters: u32,          // Stop after this many iterations
+    c_map: JsValue,          // Selected colour map
+    is_little_endian: bool,  // Is the processor little endian?
+  ) -> Result<bool, FractalError> {
+    validate_dimensions(&canvas)?;
+
+    let colour_map = parse_colour_map(&c_map)?;
+    let params = EscapeParams::new(max_iters);
+    let to_coord = pixel_to_point(&canvas, &axes_ranges);
+    let pixel_colour = |this_coord| {
+      let iters = fractal_iter(&FractalType::Mandelbrot, this_coord, &params);
+      let this_colour = &colour_map[palette_index(iters, max_iters, colour_map.len(), false)];
+      [this_colour[0], this_colour[1], this_colour[2]]
+    };
+
+    let row_len = (canvas.width * 4) as usize;
+    let mut image_data = vec![0u8; row_len * canvas.height as usize];
+    let mut completed = true;
+
+    for (iy, row_data) in image_data.chunks_exact_mut(row_len).enumerate() {
+      if self.is_cancelled() {
+        completed = false;
+        break;
+      }
+
+      draw_row(
+        row_data,
+        0,
+        iy as u32,
+        &to_coord,
+        &pixel_colour,
+        is_little_endian,
+      );
+    }
+
+    put_image(ctx, &image_data, &canvas)?;
+    Ok(completed)
+  }
+}
+
+/***********************************************************************************************************************
+ * Interactive Julia Set renderer
+ * The colour map is deserialized once when the renderer is created, so that the Julia Set can be redrawn cheaply each
+ * time the mouse moves
+ */
+#[wasm_bindgen]
+pub struct JuliaRenderer {
+  canvas: Dimensions,
+  axes_ranges: AxesRanges,
+  colour_map: Vec<Vec<u32>>,
+  is_little_endian: bool,
+}
+
+#[wasm_bindgen]
+impl JuliaRenderer {
+  #[wasm_bindgen(constructor)]
+  pub fn new(
+    canvas: Dimensions,      // Canvas dimensions
+    axes_ranges: AxesRanges, // Extent of axes ranges
+    c_map: JsValue,          // Selected colour map
+    is_little_endian: bool,  // Is the processor little endian?
+  ) -> Result<JuliaRenderer, FractalError> {
+    validate_dimensions(&canvas)?;
+
+    Ok(JuliaRenderer {
+      canvas,
+      axes_ranges,
+      colour_map: parse_colour_map(&c_map)?,
+      is_little_endian,
+    })
+  }
+
+  // Draw the Julia Set for the point c_x + i c_y
+  pub fn render(
+    &self,
+    ctx: &CanvasRenderingContext2d,
+    c_x: f64,
+    c_y: f64,
+    max_iters: u32,
+  ) -> Result<(), FractalError> {
+    put_image(
+      ctx,
+      &render_fractal(
+        &self.canvas,
+        &self.axes_ranges,
+        EscapeParams::new(max_iters),
+        &self.colour_map,
+        FractalType::Julia(Point { x: c_x, y: c_y }),
+        &RenderOptions::new(self.is_little_endian),
+      ),
+      &self.canvas,
+    )
+  }
+}
+
+/***********************************************************************************************************************
+ * Draw the Newton fractal for z^3 - 1
+ * The first three colour map entries are the colours of the basins of attraction of the three roots.  Each pixel is
+ * shaded according to how many iterations it took to converge, and points that do not converge are drawn in black
+ */
+#[wasm_bindgen]
+pub fn draw_newton(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+
+  if colour_map.len() < NEWTON_ROOTS.len() {
+    return Err(FractalError::InvalidPalette(
+      "the Newton fractal needs one colour per root".to_string(),
+    ));
+  }
+
+  draw_pixels(ctx, &canvas, &axes_ranges, is_little_endian, |this_coord| {
+    let (root_idx, iters) = newton_iter(&this_coord, &max_iters);
+    newton_colour(&colour_map, root_idx, iters, max_iters)
+  })
+}
+
+/***********************************************************************************************************************
+ * Draw the Mandelbrot Set using the exterior distance estimate
+ * The estimated distance is measured in pixels, so the boundary is drawn as a thin dark line at every zoom level, the
+ * exterior fades to white and the interior is black
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_distance(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let pixel_size = (axes_ranges.x_range.max - axes_ranges.x_range.min) / (canvas.width - 1) as f64;
+
+  draw_pixels(ctx, &canvas, &axes_ranges, is_little_endian, |this_coord| {
+    let distance = mandel_distance_estimate(&this_coord, &max_iters) / pixel_size;
+    let brightness = (distance.min(1.0).powf(0.25) * 255.0).round() as u32;
+
+    [brightness, brightness, brightness]
+  })
+}
+
+/***********************************************************************************************************************
+ * Draw an anti-aliased Mandelbrot Set
+ * Each pixel is sampled on an NxN grid of subpixel locations spaced (s + 0.5)/N across the pixel, and the resulting
+ * colours are averaged.  When N is 1, the only sample is the pixel location itself, so the output is the same as that
+ * of draw_mandel
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_aa(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+  samples_per_axis: u32,   // Number of subpixel samples in each direction
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let params = EscapeParams::new(max_iters);
+  let samples = samples_per_axis.max(1);
+  let sample_count = samples * samples;
+
+  // Size of a single pixel in the fractal's coordinate space
+  let pixel_width = (axes_ranges.x_range.max - axes_ranges.x_range.min) / (canvas.width - 1) as f64;
+  let pixel_height =
+    (axes_ranges.y_range.max - axes_ranges.y_range.min) / (canvas.height - 1) as f64;
+  let offset = |s: u32| (s as f64 + 0.5) / samples as f64 - 0.5;
+
+  draw_pixels(ctx, &canvas, &axes_ranges, is_little_endian, |this_coord| {
+    let mut totals = [0u32; 3];
+
+    for sy in 0..samples {
+      for sx in 0..samples {
+        let sample_coord = Point {
+          x: this_coord.x + pixel_width * offset(sx),
+          y: this_coord.y + pixel_height * offset(sy),
+        };
+        let iters = fractal_iter(&FractalType::Mandelbrot, sample_coord, &params);
+        let this_colour = &colour_map[palette_index(iters, max_iters, colour_map.len(), false)];
+
+        for (total, channel) in totals.iter_mut().zip(this_colour.iter()) {
+          *total += channel;
+        }
+      }
+    }
+
+    let average = |total: u32| (total + sample_count / 2) / sample_count;
+    [average(totals[0]), average(totals[1]), average(totals[2])]
+  })
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set and return statistics about the iteration counts of the pixels
+ * The statistics are gathered while the pixels are being coloured, so no extra pass over the image is needed
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_with_stats(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<RenderStats, FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let params = EscapeParams::new(max_iters);
+
+  let min_iters = Cell::new(u32::MAX);
+  let max_iters_hit = Cell::new(0);
+  let total_iters = Cell::new(0u64);
+  let interior_pixel_count = Cell::new(0);
+
+  draw_pixels(ctx, &canvas, &axes_ranges, is_little_endian, |this_coord| {
+    let iters = fractal_iter(&FractalType::Mandelbrot, this_coord, &params);
+
+    min_iters.set(min_iters.get().min(iters as u32));
+    max_iters_hit.set(max_iters_hit.get().max(iters as u32));
+    total_iters.set(total_iters.get() + iters as u64);
+
+    if iters as u32 >= max_iters {
+      interior_pixel_count.set(interior_pixel_count.get() + 1);
+    }
+
+    let this_colour = &colour_map[palette_index(iters, max_iters, colour_map.len(), false)];
+    [this_colour[0], this_colour[1], this_colour[2]]
+  })?;
+
+  Ok(RenderStats {
+    min_iters: min_iters.get(),
+    max_iters_hit: max_iters_hit.get(),
+    avg_iters: total_iters.get() as f64 / (canvas.width * canvas.height) as f64,
+    interior_pixel_count: interior_pixel_count.get(),
+  })
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set and return the total number of iterations actually calculated across all pixels
+ * Points skipped by the early bailout checks cost no iterations, and points found to have periodic orbits only cost
+ * the iterations needed to detect the cycle.  Dividing the total by the render time gives the iteration rate
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_mandel_with_iter_total(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,               // Canvas dimensions
+  axes_ranges: AxesRanges,          // Extent of axes ranges
+  max_iters: u32,                   // Stop after this many iterations
+  c_map: JsValue,                   // Selected colour map
+  is_little_endian: bool,           // Is the processor little endian?
+  early_bailout: Option<bool>,      // Skip the main cardioid and period-2 bulb? (default true)
+  detect_periodicity: Option<bool>, // Stop early on periodic orbits? (default false)
+) -> Result<u64, FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let early_bailout = early_bailout.unwrap_or(true);
+  let params = EscapeParams {
+    max_iters,
+    bailout: BAILOUT,
+    detect_periodicity: detect_periodicity.unwrap_or(false),
+    check_period_3: false,
+  };
+  let total_steps = Cell::new(0u64);
+
+  draw_pixels(ctx, &canvas, &axes_ranges, is_little_endian, |this_coord| {
+    let iters = if early_bailout && mandel_early_bailout(&this_coord) {
+      max_iters as usize
+    } else {
+      let (iters, _, steps) =
+        escape_time_with_steps(&this_coord, Point { x: 0.0, y: 0.0 }, &params, mj_step);
+
+      total_steps.set(total_steps.get() + steps as u64);
+      iters as usize
+    };
+
+    let this_colour = &colour_map[palette_index(iters, max_iters, colour_map.len(), false)];
+    [this_colour[0], this_colour[1], this_colour[2]]
+  })?;
+
+  Ok(total_steps.get())
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set using histogram equalization
+ * All the iteration counts are calculated first, then each exterior pixel is coloured according to the fraction of
+ * exterior pixels that escaped in the same number of iterations or fewer.  This spreads the colours evenly across the
+ * pixels in the image rather than evenly across the iteration counts.  Interior pixels use the last colour map entry
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_histogram(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+
+  // First pass: calculate the iteration count of every pixel
+  let iter_buffer = compute_fractal_buffer(
+    &canvas,
+    &axes_ranges,
+    &EscapeParams::new(max_iters),
+    &FractalType::Mandelbrot,
+  );
+
+  // Count the exterior pixels that escaped at each iteration, then accumulate the counts
+  let mut cumulative = escape_histogram(&iter_buffer, max_iters);
+  cumulative.pop();
+
+  for idx in 1..cumulative.len() {
+    cumulative[idx] += cumulative[idx - 1];
+  }
+
+  let exterior_count = cumulative.last().copied().unwrap_or(0).max(1) as f64;
+  let last = colour_map.len() - 1;
+  let exterior_range = last.saturating_sub(1) as f64;
+
+  // Second pass: colour each pixel by its rank
+  let image_data = render_buffer(&iter_buffer, is_little_endian, |&iters| {
+    let colour_idx = if iters < max_iters as usize {
+      (cumulative[iters] as f64 / exterior_count * exterior_range).round() as usize
+    } else {
+      last
+    };
+    let this_colour = &colour_map[colour_idx];
+
+    [this_colour[0], this_colour[1], this_colour[2]]
+  });
+
+  put_image(ctx, &image_data, &canvas)
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set coloured by how closely the orbit of each point approaches an orbit trap
+ * Distances from 0 to 1 are spread across the colour map, and larger distances use the last colour map entry
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_orbit_trap(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+  trap: JsValue,           // Orbit trap
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+
+  let trap: OrbitTrap = from_js_value(
+    &trap,
+    FractalError::InvalidParameter("orbit trap must be a Point, Cross or Circle".to_string()),
+  )?;
+  let last = (colour_map.len() - 1) as f64;
+
+  draw_pixels(ctx, &canvas, &axes_ranges, is_little_endian, |this_coord| {
+    let distance = mandel_orbit_trap(&this_coord, &trap, &max_iters);
+    interpolate_colour(&colour_map, distance.min(1.0) * last, max_iters, false)
+  })
+}
+
+/***********************************************************************************************************************
+ * Progressive Mandelbrot Set renderer
+ * The canvas is divided into square tiles that are drawn one at a time, so that JavaScript can draw the next tile from
+ * a requestAnimationFrame callback and keep the UI responsive.  To cancel the render, simply stop calling
+ * render_next_tile
+ */
+#[wasm_bindgen]
+pub struct TileRenderer {
+  canvas: Dimensions,
+  axes_ranges: AxesRanges,
+  max_iters: u32,
+  colour_map: Vec<Vec<u32>>,
+  is_little_endian: bool,
+  tile_size: u32,
+  next_tile: u32,
+}
+
+#[wasm_bindgen]
+impl TileRenderer {
+  #[wasm_bindgen(constructor)]
+  pub fn new(
+    canvas: Dimensions,      // Canvas dimensions
+    axes_ranges: AxesRanges, // Extent of axes ranges
+    max_iters: u32,          // Stop after this many iterations
+    c_map: JsValue,          // Selected colour map
+    is_little_endian: bool,  // Is the processor little endian?
+    tile_size: u32,          // Width and height of each tile in pixels
+  ) -> Result<TileRenderer, FractalError> {
+    validate_dimensions(&canvas)?;
+
+    Ok(TileRenderer {
+      canvas,
+      axes_ranges,
+      max_iters,
+      colour_map: parse_colour_map(&c_map)?,
+      is_little_endian,
+      tile_size: tile_size.max(1),
+      next_tile: 0,
+    })
+  }
+
+  // Draw the next tile, then return whether there are any tiles left to draw
+  pub fn render_next_tile(&mut self, ctx: &CanvasRenderingContext2d) -> Result<bool, FractalError> {
+    let tiles_across = self.canvas.width.div_ceil(self.tile_size);
+    let tiles_down = self.canvas.height.div_ceil(self.tile_size);
+
+    if self.next_tile >= tiles_across * tiles_down {
+      return Ok(false);
+    }
+
+    let x = (self.next_tile % tiles_across) * self.tile_size;
+    let y = (self.next_tile / tiles_across) * self.tile_size;
+    let tile = Region {
+      x,
+      y,
+      width: self.tile_size.min(self.canvas.width - x),
+      height: self.tile_size.min(self.canvas.height - y),
+    };
+
+    let params = EscapeParams::new(self.max_iters);
+    let colour_map = &self.colour_map;
+
+    let image_data = render_region(
+      &self.canvas,
+      &self.axes_ranges,
+      &tile,
+      self.is_little_endian,
+      |this_coord| {
+        let iters = fractal_iter(&FractalType::Mandelbrot, this_coord, &params);
+        let this_colour =
+          &colour_map[palette_index(iters, params.max_iters, colour_map.len(), false)];
+        [this_colour[0], this_colour[1], this_colour[2]]
+      },
+    );
+    put_image_at(ctx, &image_data, &tile)?;
+
+    self.next_tile += 1;
+    Ok(self.next_tile < tiles_across * tiles_down)
+  }
+}
+
+/***********************************************************************************************************************
+ * Calculate the axes ranges needed to zoom into a rectangle drawn on the canvas
+ * The rectangle's corners can be given in any order.  The new ranges preserve the canvas' aspect ratio by expanding
+ * the shorter side of the rectangle, and if the rectangle has no area, the current ranges are returned unchanged
+ */
+#[wasm_bindgen]
+pub fn zoom_to_rect(
+  current: AxesRanges, // Current extent of axes ranges
+  canvas: Dimensions,  // Canvas dimensions
+  x0: f64,             // Pixel location of one corner of the rectangle
+  y0: f64,             // "
+  x1: f64,             // Pixel location of the opposite corner
+  y1: f64,             // "
+) -> AxesRanges {
+  if x0 == x1 && y0 == y1 {
+    return current;
+  }
+
+  // Scale pixel locations to the fractal's coordinate space the same way as pixel_to_point
+  let x_scale = (current.x_range.max - current.x_range.min) / (canvas.width.max(2) - 1) as f64;
+  let y_scale = (current.y_range.max - current.y_range.min) / (canvas.height.max(2) - 1) as f64;
+  let to_x = |pos: f64| current.x_range.min + x_scale * pos;
+  let to_y = |pos: f64| current.y_range.min + y_scale * pos;
+
+  fit_to_aspect(
+    to_x(x0.min(x1)),
+    to_x(x0.max(x1)),
+    to_y(y0.min(y1)),
+    to_y(y0.max(y1)),
+    &canvas,
+  )
+}
+
+/***********************************************************************************************************************
+ * Calculate the axes ranges for a view centred on a point
+ * The longer axis of the canvas extends zoom either side of the centre, and the shorter axis is scaled so that each
+ * pixel covers the same distance along both axes.  A square canvas gets a half-width of zoom on both axes
+ */
+#[wasm_bindgen]
+pub fn fit_ranges_to_aspect(
+  center: Point,      // Point at the centre of the canvas
+  zoom: f64,          // Half the width of the longer axis range
+  canvas: Dimensions, // Canvas dimensions
+) -> AxesRanges {
+  let x_pixels = (canvas.width.max(2) - 1) as f64;
+  let y_pixels = (canvas.height.max(2) - 1) as f64;
+  let pixel_size = 2.0 * zoom.abs() / x_pixels.max(y_pixels);
+  let x_half = pixel_size * x_pixels / 2.0;
+  let y_half = pixel_size * y_pixels / 2.0;
+
+  AxesRanges {
+    x_range: Range {
+      min: center.x - x_half,
+      max: center.x + x_half,
+    },
+    y_range: Range {
+      min: center.y - y_half,
+      max: center.y + y_half,
+    },
+  }
+}
+
+/***********************************************************************************************************************
+ * Shift the axes ranges to follow the mouse pointer being dragged across the canvas by the given number of pixels
+ * The image moves with the pointer, so the point that was under the pointer when the drag started stays under it.  Row
+ * 0 of the canvas is drawn at the minimum of the Y axis range, so dragging downwards decreases both Y axis values
+ */
+#[wasm_bindgen]
+pub fn pan_ranges(
+  current: AxesRanges, // Current extent of axes ranges
+  canvas: Dimensions,  // Canvas dimensions
+  dx_pixels: f64,      // Distance dragged to the right in pixels
+  dy_pixels: f64,      // Distance dragged downwards in pixels
+) -> AxesRanges {
+  if canvas.width < 2 || canvas.height < 2 {
+    return current;
+  }
+
+  // Scale the drag distance to the fractal's coordinate space the same way as when drawing
+  let to_coord = fractional_pixel_to_point(&canvas, &current);
+  let (origin, moved) = (to_coord(0.0, 0.0), to_coord(dx_pixels, dy_pixels));
+  let (dx, dy) = (moved.x - origin.x, moved.y - origin.y);
+
+  AxesRanges {
+    x_range: Range {
+      min: current.x_range.min - dx,
+      max: current.x_range.max - dx,
+    },
+    y_range: Range {
+      min: current.y_range.min - dy,
+      max: current.y_range.max - dy,
+    },
+  }
+}
+
+/***********************************************************************************************************************
+ * Draw the Phoenix fractal z_{n+1} = z_n^2 + c + p * z_{n-1}
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_phoenix(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,              // Canvas dimensions
+  axes_ranges: AxesRanges,         // Extent of axes ranges
+  max_iters: u32,                  // Stop after this many iterations
+  c_map: JsValue,                  // Selected colour map
+  is_little_endian: bool,          // Is the processor little endian?
+  p_x: f64,                        // Real part of the feedback parameter p
+  p_y: f64,                        // Imaginary part of the feedback parameter p
+  bailout: Option<f64>,            // Escape when |z|^2 exceeds this value (default 4.0)
+  wrap_palette: Option<bool>,      // Repeat the colour map instead of clamping? (default false)
+  preserve_aspect: Option<bool>,   // Keep the canvas aspect ratio? (default false)
+  color_scale: Option<ColorScale>, // Iteration to colour mapping (default Linear)
+  render_mask: Option<RenderMask>, // Pixels to draw; others are transparent (default All)
+  alpha: Option<u8>,               // Opacity of the pixels drawn (default 255)
+  interior_color: Option<Vec<u8>>, // RGB colour of points inside the set (default last colour map entry)
+) -> Result<(), FractalError> {
+  draw_fractal(
+    ctx,
+    canvas,
+    axes_ranges,
+    EscapeParams {
+      max_iters,
+      bailout: valid_bailout(bailout),
+      detect_periodicity: false,
+      check_period_3: false,
+    },
+    c_map,
+    FractalType::Phoenix(Point { x: p_x, y: p_y }),
+    RenderOptions {
+      wrap_palette: wrap_palette.unwrap_or(false),
+      preserve_aspect: preserve_aspect.unwrap_or(false),
+      color_scale: color_scale.unwrap_or(ColorScale::Linear),
+      mask: render_mask.unwrap_or(RenderMask::All),
+      alpha: alpha.unwrap_or(0xff),
+      interior_colour: parse_interior_colour(interior_color)?,
+      ..RenderOptions::new(is_little_endian)
+    },
+  )
+}
+
+/***********************************************************************************************************************
+ * Calculate the Mandelbrot Set and return its RGBA byte data without drawing it
+ * This does not need a CanvasRenderingContext2d, so it can be called from a Web Worker.  The bytes are identical to
+ * those that draw_mandel would draw with its default options, and can be wrapped in an ImageData object or uploaded to a
+ * WebGL texture
+ */
+#[wasm_bindgen]
+pub fn render_mandel_rgba(
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<Vec<u8>, FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+
+  Ok(render_fractal(
+    &canvas,
+    &axes_ranges,
+    EscapeParams::new(max_iters),
+    &colour_map,
+    FractalType::Mandelbrot,
+    &RenderOptions::new(is_little_endian),
+  ))
+}
+
+/***********************************************************************************************************************
+ * Calculate the Mandelbrot Set and return it encoded as a PNG file
+ * JavaScript can wrap the returned bytes in a Blob to save the image without needing to call canvas.toDataURL
+ */
+#[wasm_bindgen]
+pub fn render_mandel_png(
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<Vec<u8>, FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let mut image_data = render_fractal(
+    &canvas,
+    &axes_ranges,
+    EscapeParams::new(max_iters),
+    &colour_map,
+    FractalType::Mandelbrot,
+    &RenderOptions::new(is_little_endian),
+  );
+
+  // PNG files always store pixels in RGBA byte order, so undo the reordering used for big endian canvas data
+  if !is_little_endian {
+    for pixel in image_data.chunks_exact_mut(4) {
+      pixel.reverse();
+    }
+  }
+
+  Ok(png::encode_rgba(canvas.width, canvas.height, &image_data))
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set using a colour map passed as a Uint8Array of RGB values
+ * This is faster than draw_mandel because the colour map is read directly from JavaScript memory instead of being
+ * deserialized on every call
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_rgb(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  palette: &[u8],          // Selected colour map as a flat array of RGB values
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  draw_fractal_rgb(
+    ctx,
+    &canvas,
+    &axes_ranges,
+    EscapeParams::new(max_iters),
+    palette,
+    is_little_endian,
+    FractalType::Mandelbrot,
+  )
+}
+
+/***********************************************************************************************************************
+ * Draw a Julia Set using a colour map passed as a Uint8Array of RGB values
+ */
+#[wasm_bindgen]
+pub fn draw_julia_rgb(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  mouse_loc: Point,        // Mouse pointer coords on Mandelbrot set
+  max_iters: u32,          // Stop after this many iterations
+  palette: &[u8],          // Selected colour map as a flat array of RGB values
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  draw_fractal_rgb(
+    ctx,
+    &canvas,
+    &axes_ranges,
+    EscapeParams::new(max_iters),
+    palette,
+    is_little_endian,
+    FractalType::Julia(mouse_loc),
+  )
+}
+
+/***********************************************************************************************************************
+ * Draw a Lyapunov fractal for a sequence of A's and B's such as "AABAB"
+ * Each pixel is a pair of values (a, b) for the growth rate r of the logistic map.  Stable regions (negative exponent)
+ * are drawn in shades of yellow, and chaotic regions (positive exponent) are drawn in shades of blue
+ */
+#[wasm_bindgen]
+pub fn draw_lyapunov(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,     // Canvas dimensions
+  a_range: Range,         // Range of values for a plotted along the X axis
+  b_range: Range,         // Range of values for b plotted along the Y axis
+  sequence: String,       // Order in which the values of a and b are used
+  max_iters: u32,         // Number of iterations used to measure the exponent
+  is_little_endian: bool, // Is the processor little endian?
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let sequence = parse_lyapunov_sequence(&sequence)?;
+  let axes_ranges = AxesRanges {
+    x_range: a_range,
+    y_range: b_range,
+  };
+
+  draw_pixels(ctx, &canvas, &axes_ranges, is_little_endian, |this_coord| {
+    let exponent = lyapunov_exponent(&this_coord, &sequence, &max_iters);
+
+    if exponent <= 0.0 {
+      let brightness = 1.0 - exponent.exp();
+      [(brightness * 255.0) as u32, (brightness * 224.0) as u32, 0]
+    } else {
+      let brightness = 1.0 - (-exponent).exp();
+      [0, 0, (brightness * 255.0) as u32]
+    }
+  })
+}
+
+/***********************************************************************************************************************
+ * Interpolate between two views for one frame of a zoom animation, where t runs from 0 (start) to 1 (target)
+ * The width of each axis range changes geometrically so that the zoom appears to happen at a constant speed, while
+ * the centre of the view moves linearly
+ */
+#[wasm_bindgen]
+pub fn interpolate_ranges(start: AxesRanges, target: AxesRanges, t: f64) -> AxesRanges {
+  if t <= 0.0 {
+    return start;
+  }
+
+  if t >= 1.0 {
+    return target;
+  }
+
+  let interpolate = |from: &Range, to: &Range| {
+    let (from_width, to_width) = (from.max - from.min, to.max - to.min);
+    let (from_mid, to_mid) = ((from.min + from.max) / 2.0, (to.min + to.max) / 2.0);
+
+    let half_width = from_width * (to_width / from_width).powf(t) / 2.0;
+    let mid = from_mid + (to_mid - from_mid) * t;
+
+    Range {
+      min: mid - half_width,
+      max: mid + half_width,
+    }
+  };
+
+  AxesRanges {
+    x_range: interpolate(&start.x_range, &target.x_range),
+    y_range: interpolate(&start.y_range, &target.y_range),
+  }
+}
+
+/***********************************************************************************************************************
+ * Is the point x + iy a member of the Mandelbrot Set?
+ * A point is treated as a member if it does not escape within max_iters iterations
+ */
+#[wasm_bindgen]
+pub fn is_in_mandelbrot(x: f64, y: f64, max_iters: u32) -> bool {
+  mandel_iter(&Point { x, y }, &EscapeParams::new(max_iters)) >= max_iters as usize
+}
+
+/***********************************************************************************************************************
+ * Return the orbit of the point c_x + i c_y under z = z^2 + c, starting from z = 0
+ * The values of z are flattened into [x0, y0, x1, y1, ...].  The orbit ends with the first value of z that escapes,
+ * or after max_iters iterations if it never escapes
+ */
+#[wasm_bindgen]
+pub fn mandel_orbit(c_x: f64, c_y: f64, max_iters: u32) -> Vec<f64> {
+  let c = Complex::new(c_x, c_y);
+  let mut z = Complex::new(0.0, 0.0);
+  let mut orbit = vec![z.re, z.im];
+
+  for _ in 0..max_iters {
+    z = mj_step(z, c);
+    orbit.push(z.re);
+    orbit.push(z.im);
+
+    if z.abs_sq() > BAILOUT {
+      break;
+    }
+  }
+
+  orbit
+}
+
+/***********************************************************************************************************************
+ * Suggest an iteration limit for the given axes ranges, so that deeper zooms automatically get more iterations
+ * The limit is max(MIN_SUGGESTED_ITERS, ln(1 / x range width) * ITERS_PER_ZOOM_LOG)
+ */
+#[wasm_bindgen]
+pub fn suggest_max_iters(axes_ranges: AxesRanges) -> u32 {
+  let width = axes_ranges.x_range.max - axes_ranges.x_range.min;
+
+  if width.is_finite() && width > 0.0 {
+    let suggested = (1.0 / width).ln() * ITERS_PER_ZOOM_LOG;
+    (suggested.min(u32::MAX as f64) as u32).max(MIN_SUGGESTED_ITERS)
+  } else {
+    MIN_SUGGESTED_ITERS
+  }
+}
+
+/***********************************************************************************************************************
+ * Is the Julia Set for the parameter c_x + i c_y connected?
+ * A Julia Set is connected if its parameter is a member of the Mandelbrot Set; otherwise it is a disconnected cloud of
+ * points (Cantor dust)
+ */
+#[wasm_bindgen]
+pub fn is_julia_connected(c_x: f64, c_y: f64, max_iters: u32) -> bool {
+  is_in_mandelbrot(c_x, c_y, max_iters)
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set shaded as if it were an embossed surface lit from the given direction
+ * The light angle is measured anticlockwise from the positive X axis and the elevation upwards from the plane of the
+ * canvas, both in degrees.  Exterior colours are scaled by the Lambertian brightness of the surface normal, and the
+ * interior is left unshaded
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_mandel_shaded(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+  light_angle: f64,        // Direction of the light source in degrees
+  light_elevation: f64,    // Height of the light source above the canvas in degrees
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let (angle, elevation) = (light_angle.to_radians(), light_elevation.to_radians());
+  let light = (
+    elevation.cos() * angle.cos(),
+    elevation.cos() * angle.sin(),
+    elevation.sin(),
+  );
+
+  draw_pixels(ctx, &canvas, &axes_ranges, is_little_endian, |this_coord| {
+    let (iters, normal) = mandel_normal(&this_coord, &max_iters);
+    let this_colour = &colour_map[palette_index(iters, max_iters, colour_map.len(), false)];
+
+    // The surface normal is (u.re, u.im, 1) scaled to unit length
+    let brightness = match normal {
+      Some(u) => ((u.re * light.0 + u.im * light.1 + light.2) / 2.0_f64.sqrt()).max(0.0),
+      None => 1.0,
+    };
+    let shade = |channel: u32| (channel as f64 * brightness).round() as u32;
+
+    [
+      shade(this_colour[0]),
+      shade(this_colour[1]),
+      shade(this_colour[2]),
+    ]
+  })
+}
+
+/***********************************************************************************************************************
+ * Draw one rectangular tile of a Mandelbrot Set that spans several canvases
+ * Pixel locations are scaled as if the tile were part of full_canvas, so tiles drawn with the same full_canvas and
+ * full_axes join up seamlessly.  The tile is written to the context at (tile_x, tile_y)
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_mandel_region(
+  ctx: &CanvasRenderingContext2d,
+  full_canvas: Dimensions, // Dimensions of the entire viewport
+  full_axes: AxesRanges,   // Extent of axes ranges across the entire viewport
+  tile_x: u32,             // Pixel location of the tile's top left corner
+  tile_y: u32,             // "
+  tile_w: u32,             // Width of the tile in pixels
+  tile_h: u32,             // Height of the tile in pixels
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  if tile_w == 0
+    || tile_h == 0
+    || tile_x.saturating_add(tile_w) > full_canvas.width
+    || tile_y.saturating_add(tile_h) > full_canvas.height
+  {
+    return Err(FractalError::InvalidDimensions(
+      "tile must be non-empty and lie within the canvas".to_string(),
+    ));
+  }
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let params = EscapeParams::new(max_iters);
+  let tile = Region {
+    x: tile_x,
+    y: tile_y,
+    width: tile_w,
+    height: tile_h,
+  };
+
+  let image_data = render_region(
+    &full_canvas,
+    &full_axes,
+    &tile,
+    is_little_endian,
+    |this_coord| {
+      let iters = fractal_iter(&FractalType::Mandelbrot, this_coord, &params);
+      let this_colour = &colour_map[palette_index(iters, max_iters, colour_map.len(), false)];
+      [this_colour[0], this_colour[1], this_colour[2]]
+    },
+  );
+
+  put_image_at(ctx, &image_data, &tile)
+}
+
+/***********************************************************************************************************************
+ * Draw grid lines over the fractal at multiples of the given spacing in the fractal's coordinate space
+ * The spacing is first rounded to a nice value (1, 2 or 5 times a power of ten), and if the origin is visible, it is
+ * labelled
+ */
+#[wasm_bindgen]
+pub fn draw_axes_overlay(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  spacing: f64,            // Approximate distance between grid lines
+  color: String,           // CSS colour of the grid lines and label
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  if !spacing.is_finite() || spacing <= 0.0 {
+    return Err(FractalError::InvalidParameter(
+      "grid spacing must be greater than zero".to_string(),
+    ));
+  }
+
+  let spacing = nice_spacing(spacing);
+  let to_pixel = point_to_pixel(&canvas, &axes_ranges);
+  let (width, height) = ((canvas.width - 1) as f64, (canvas.height - 1) as f64);
+
+  // Return the multiples of spacing that lie within a range
+  let multiples = |range: &Range| {
+    let first = (range.min / spacing).ceil() as i64;
+    let last = (range.max / spacing).floor() as i64;
+    (first..=last).map(|n| n as f64 * spacing)
+  };
+
+  // Refuse to draw grid lines so closely spaced that they would cover the entire canvas
+  let line_count =
+    multiples(&axes_ranges.x_range).count() + multiples(&axes_ranges.y_range).count();
+
+  if line_count > (canvas.width + canvas.height) as usize / 2 {
+    return Err(FractalError::InvalidParameter(
+      "grid spacing gives too many grid lines".to_string(),
+    ));
+  }
+
+  ctx.set_stroke_style_str(&color);
+  ctx.set_fill_style_str(&color);
+  ctx.set_line_width(1.0);
+  ctx.begin_path();
+
+  for x in multiples(&axes_ranges.x_range) {
+    let (px, _) = to_pixel(x, 0.0);
+    ctx.move_to(px, 0.0);
+    ctx.line_to(px, height);
+  }
+
+  for y in multiples(&axes_ranges.y_range) {
+    let (_, py) = to_pixel(0.0, y);
+    ctx.move_to(0.0, py);
+    ctx.line_to(width, py);
+  }
+
+  ctx.stroke();
+
+  let (origin_x, origin_y) = to_pixel(0.0, 0.0);
+
+  if (0.0..=width).contains(&origin_x) && (0.0..=height).contains(&origin_y) {
+    ctx.fill_text("(0, 0)", origin_x + 3.0, origin_y - 3.0)?;
+  }
+
+  Ok(())
+}
+
+/***********************************************************************************************************************
+ * Search the area around a seed point for a location on the boundary of the Mandelbrot Set
+ * Starting outside the set, the search keeps moving to whichever neighbouring point has the highest iteration count.
+ * Starting inside the set, the neighbours are looked for further and further away.  Either way, once one point inside
+ * the set and one outside have been found next to each other, the boundary between them is found by bisection.  The
+ * search never strays further than search_radius from the seed point
+ */
+#[wasm_bindgen]
+pub fn find_boundary_point(near_x: f64, near_y: f64, search_radius: f64, max_iters: u32) -> Point {
+  const DIRECTIONS: usize = 8;
+  const MAX_STEPS: u32 = 1000;
+
+  let params = EscapeParams::new(max_iters);
+  let is_interior = |point: &Point| mandel_iter(point, &params) >= max_iters as usize;
+  let seed = Point {
+    x: near_x,
+    y: near_y,
+  };
+  let radius = search_radius.abs();
+  let min_step = radius * 1e-6;
+  let mut step = radius / 8.0;
+  let mut current = seed;
+  let mut current_iters = mandel_iter(&current, &params);
+
+  // Look for a neighbouring pair of points where one is inside the set and the other is outside
+  let mut straddle = None;
+
+  for _ in 0..MAX_STEPS {
+    if step <= min_step || step > radius {
+      break;
+    }
+
+    let neighbours: Vec<(Point, usize)> = (0..DIRECTIONS)
+      .map(|idx| {
+        let angle = idx as f64 * std::f64::consts::TAU / DIRECTIONS as f64;
+        Point {
+          x: current.x + step * angle.cos(),
+          y: current.y + step * angle.sin(),
+        }
+      })
+      .filter(|point| sum_of_squares(point.x - seed.x, point.y - seed.y) <= radius * radius)
+      .map(|point| (point, mandel_iter(&point, &params)))
+      .collect();
+
+    let current_is_interior = current_iters >= max_iters as usize;
+
+    if let Some(&(other, _)) = neighbours
+      .iter()
+      .find(|(_, iters)| (*iters >= max_iters as usize) != current_is_interior)
+    {
+      straddle = Some((current, other));
+      break;
+    }
+
+    if current_is_interior {
+      // Every neighbour is also inside the set, so look further afield
+      step *= 2.0;
+    } else {
+      // Every neighbour is outside the set, so head towards the highest iteration count
+      match neighbours.iter().max_by_key(|(_, iters)| *iters) {
+        Some(&(best, best_iters)) if best_iters > current_iters => {
+          current = best;
+          current_iters = best_iters;
+        }
+        _ => step /= 2.0,
+      }
+    }
+  }
+
+  match straddle {
+    Some((from, to)) => {
+      // Bisect the line between the two points, keeping one end inside the set and the other outside
+      let (mut outside, mut inside) = if is_interior(&from) {
+        (to, from)
+      } else {
+        (from, to)
+      };
+
+      while sum_of_squares(outside.x - inside.x, outside.y - inside.y) > min_step * min_step {
+        let mid = Point {
+          x: (outside.x + inside.x) / 2.0,
+          y: (outside.y + inside.y) / 2.0,
+        };
+
+        if is_interior(&mid) {
+          inside = mid;
+        } else {
+          outside = mid;
+        }
+      }
+
+      outside
+    }
+    None => current,
+  }
+}
+
+/***********************************************************************************************************************
+ * Resample a colour map to exactly target_len entries
+ * This allows the colour map length to match max_iters so that every iteration value gets a distinct colour
+ */
+#[wasm_bindgen]
+pub fn resample_palette(c_map: JsValue, target_len: usize) -> Result<JsValue, JsValue> {
+  let colour_map = parse_colour_map(&c_map)?;
+  to_js_value(&resample_colour_map(&colour_map, target_len))
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set at the resolution of a HiDPI canvas' backing store
+ * The canvas dimensions are given in CSS pixels and are multiplied by the device pixel ratio, so the image drawn is
+ * round(width * pixel_ratio) x round(height * pixel_ratio) pixels.  The axes ranges are unchanged, so only the density
+ * at which the fractal is sampled increases
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_hidpi(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions in CSS pixels
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+  pixel_ratio: f64,        // Device pixels per CSS pixel (window.devicePixelRatio)
+) -> Result<(), FractalError> {
+  if !pixel_ratio.is_finite() || pixel_ratio <= 0.0 {
+    return Err(FractalError::InvalidParameter(
+      "pixel ratio must be greater than zero".to_string(),
+    ));
+  }
+
+  let scaled_canvas = Dimensions {
+    width: (canvas.width as f64 * pixel_ratio).round() as u32,
+    height: (canvas.height as f64 * pixel_ratio).round() as u32,
+  };
+
+  draw_fractal(
+    ctx,
+    scaled_canvas,
+    axes_ranges,
+    EscapeParams::new(max_iters),
+    c_map,
+    FractalType::Mandelbrot,
+    RenderOptions::new(is_little_endian),
+  )
+}
+
+/***********************************************************************************************************************
+ * Return the point at the given angle (in radians) on a circle in the complex plane
+ * Sweeping the angle from 0 to 2π and passing each point to draw_julia as mouse_loc animates a Julia Set whose
+ * parameter c travels around the circle
+ */
+#[wasm_bindgen]
+pub fn julia_c_on_circle(center_x: f64, center_y: f64, radius: f64, angle: f64) -> Point {
+  Point {
+    x: center_x + radius * angle.cos(),
+    y: center_y + radius * angle.sin(),
+  }
+}
+
+/***********************************************************************************************************************
+ * Return axes ranges that frame the Julia Set for the parameter c
+ * A grid of sample_density x sample_density points covering the square from -2 - 2i to 2 + 2i is tested, and the
+ * bounding box of the points that do not escape is enlarged by one grid spacing plus a small margin.  If every sample
+ * escapes, the whole square is returned
+ */
+#[wasm_bindgen]
+pub fn julia_bounding_box(c: Point, sample_density: u32, max_iters: u32) -> AxesRanges {
+  let samples = sample_density.max(2);
+  let spacing = 2.0 * JULIA_SAMPLE_RANGE / (samples - 1) as f64;
+  let params = EscapeParams::new(max_iters);
+  let mut bounds: Option<(f64, f64, f64, f64)> = None;
+
+  for iy in 0..samples {
+    for ix in 0..samples {
+      let this_coord = Point {
+        x: -JULIA_SAMPLE_RANGE + ix as f64 * spacing,
+        y: -JULIA_SAMPLE_RANGE + iy as f64 * spacing,
+      };
+
+      if fractal_iter(&FractalType::Julia(c), this_coord, &params) >= max_iters as usize {
+        let (x, y) = (this_coord.x, this_coord.y);
+
+        bounds = Some(match bounds {
+          Some((x_min, x_max, y_min, y_max)) => {
+            (x_min.min(x), x_max.max(x), y_min.min(y), y_max.max(y))
+          }
+          None => (x, x, y, y),
+        });
+      }
+    }
+  }
+
+  let (x_min, x_max, y_min, y_max) = match bounds {
+    Some(bounds) => bounds,
+    None => (
+      -JULIA_SAMPLE_RANGE,
+      JULIA_SAMPLE_RANGE,
+      -JULIA_SAMPLE_RANGE,
+      JULIA_SAMPLE_RANGE,
+    ),
+  };
+
+  let x_margin = spacing + (x_max - x_min) * JULIA_FRAME_MARGIN;
+  let y_margin = spacing + (y_max - y_min) * JULIA_FRAME_MARGIN;
+
+  AxesRanges {
+    x_range: Range {
+      min: x_min - x_margin,
+      max: x_max + x_margin,
+    },
+    y_range: Range {
+      min: y_min - y_margin,
+      max: y_max + y_margin,
+    },
+  }
+}
+
+/***********************************************************************************************************************
+ * Round both coordinates of a point to the nearest multiple of the grid spacing
+ * If the grid spacing is not a positive number, the point is returned unchanged
+ */
+#[wasm_bindgen]
+pub fn snap_to_grid(p: Point, grid: f64) -> Point {
+  if !grid.is_finite() || grid <= 0.0 {
+    return p;
+  }
+
+  Point {
+    x: (p.x / grid).round() * grid,
+    y: (p.y / grid).round() * grid,
+  }
+}
+
+/***********************************************************************************************************************
+ * Build a colour map of the given length in which each channel follows a sine wave of its own frequency
+ * Using frequencies that are multiples of 2pi gives a colour map whose last entry blends smoothly back into the first
+ */
+#[wasm_bindgen]
+pub fn sine_palette(
+  length: usize, // Number of colour map entries
+  r_freq: f64,   // Frequency of the red channel
+  g_freq: f64,   // Frequency of the green channel
+  b_freq: f64,   // Frequency of the blue channel
+  phase: f64,    // Phase shift applied to all three channels
+) -> Result<JsValue, JsValue> {
+  to_js_value(&sine_colour_map(length, [r_freq, g_freq, b_freq], phase))
+}
+
+/***********************************************************************************************************************
+ * Build a colour map of the given length from an array of gradient stops such as
+ * [{"pos": 0.0, "r": 0, "g": 0, "b": 64}, {"pos": 1.0, "r": 255, "g": 255, "b": 255}]
+ * The stops do not need to be sorted
+ */
+#[wasm_bindgen]
+pub fn build_palette(stops: JsValue, length: usize) -> Result<JsValue, JsValue> {
+  let mut stops: Vec<GradientStop> = from_js_value(
+    &stops,
+    FractalError::InvalidPalette("gradient stops must be [{pos, r, g, b}]".to_string()),
+  )?;
+
+  if stops.is_empty() {
+    return Err(FractalError::InvalidPalette("gradient has no stops".to_string()).into());
+  }
+
+  to_js_value(&gradient_colour_map(&mut stops, length))
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set using perturbation theory
+ * This gives a cleaner image than draw_mandel once the axes ranges are so narrow that neighbouring pixel locations can
+ * no longer be told apart accurately (widths below about 1e-13)
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_perturbation(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let iter_buffer =
+    compute_perturbation_buffer(&canvas, &axes_ranges, &EscapeParams::new(max_iters));
+
+  let image_data = render_buffer(&iter_buffer, is_little_endian, |&iters| {
+    let this_colour = &colour_map[palette_index(iters, max_iters, colour_map.len(), false)];
+    [this_colour[0], this_colour[1], this_colour[2]]
+  });
+
+  put_image(ctx, &image_data, &canvas)
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set using Mariani-Silver subdivision
+ * Rectangles whose borders all have the same iteration value are filled without calculating the pixels inside them.
+ * This is much faster for views dominated by large areas of a single colour, but a rectangle with a solid border can
+ * occasionally hide fine detail inside it, so this is best used for previews
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_subdivided(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let iter_buffer = compute_subdivided_buffer(&canvas, &axes_ranges, &EscapeParams::new(max_iters));
+
+  let image_data = render_buffer(&iter_buffer, is_little_endian, |&iters| {
+    let this_colour = &colour_map[palette_index(iters, max_iters, colour_map.len(), false)];
+    [this_colour[0], this_colour[1], this_colour[2]]
+  });
+
+  put_image(ctx, &image_data, &canvas)
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set using stripe average colouring
+ * The stripe averages of exterior points are spread across the colour map, producing ribbon-like bands that follow the
+ * set's filaments.  Interior points use the same colour as they do in draw_mandel
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_stripes(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+  stripe_freq: f64,        // Number of stripes per revolution around the origin
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let last = (colour_map.len() - 1) as f64;
+  let interior = &colour_map[palette_index(max_iters as usize, max_iters, colour_map.len(), false)];
+
+  draw_pixels(
+    ctx,
+    &canvas,
+    &axes_ranges,
+    is_little_endian,
+    |this_coord| match mandel_stripe_average(&this_coord, stripe_freq, &max_iters) {
+      Some(average) => interpolate_colour(&colour_map, average * last, max_iters, false),
+      None => [interior[0], interior[1], interior[2]],
+    },
+  )
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set coloured by the angle of z at the moment each point escapes
+ * Angles from 0 to 2pi are spread across the colour map, which reveals the structure of the external rays; a colour
+ * map whose first and last colours match avoids a visible seam.  Interior points use the same colour as in draw_mandel
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_angle(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let last = (colour_map.len() - 1) as f64;
+  let interior = &colour_map[palette_index(max_iters as usize, max_iters, colour_map.len(), false)];
+
+  draw_pixels(
+    ctx,
+    &canvas,
+    &axes_ranges,
+    is_little_endian,
+    |this_coord| match mandel_final_angle(&this_coord, &max_iters) {
+      Some(angle) => {
+        let value = angle / (2.0 * std::f64::consts::PI) * last;
+        interpolate_colour(&colour_map, value, max_iters, false)
+      }
+      None => [interior[0], interior[1], interior[2]],
+    },
+  )
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set coloured by the potential of each exterior point
+ * The colour comes from -log2 of the potential, which falls smoothly towards the boundary of the set.  If a contour
+ * spacing is given, this value is taken modulo the spacing and each band is spread across the whole colour map, so the
+ * edges of the bands form equipotential lines.  Interior points use the same colour as in draw_mandel
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_potential(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,           // Canvas dimensions
+  axes_ranges: AxesRanges,      // Extent of axes ranges
+  max_iters: u32,               // Stop after this many iterations
+  c_map: JsValue,               // Selected colour map
+  is_little_endian: bool,       // Is the processor little endian?
+  contour_spacing: Option<f64>, // Width of each band of equipotential contours (default no contours)
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let last = (colour_map.len() - 1) as f64;
+  let interior = &colour_map[palette_index(max_iters as usize, max_iters, colour_map.len(), false)];
+
+  let spacing = match contour_spacing {
+    Some(spacing) if !(spacing.is_finite() && spacing > 0.0) => {
+      return Err(FractalError::InvalidParameter(
+        "contour spacing must be greater than 0".to_string(),
+      ))
+    }
+    spacing => spacing,
+  };
+
+  draw_pixels(ctx, &canvas, &axes_ranges, is_little_endian, |this_coord| {
+    let potential = mandel_potential(&this_coord, &max_iters);
+
+    if potential <= 0.0 {
+      return [interior[0], interior[1], interior[2]];
+    }
+
+    let level = (-potential.log2()).max(0.0);
+    let value = match spacing {
+      Some(spacing) => level.rem_euclid(spacing) / spacing * last,
+      None => level.min((max_iters - 1) as f64),
+    };
+
+    interpolate_colour(&colour_map, value, max_iters, false)
+  })
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set using binary decomposition
+ * The colour map is split into two halves.  Exterior points whose value of z had a positive imaginary part when they
+ * escaped are coloured from the second half, and all other exterior points from the first half, which reveals the
+ * cells between the external rays.  Interior points use the same colour as in draw_mandel
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_binary_decomp(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+
+  if colour_map.len() < 2 {
+    return Err(FractalError::InvalidPalette(
+      "binary decomposition needs at least 2 colours".to_string(),
+    ));
+  }
+
+  let half = colour_map.len() / 2;
+
+  draw_pixels(ctx, &canvas, &axes_ranges, is_little_endian, |this_coord| {
+    let (iters, positive) = mandel_binary_decomp(&this_coord, &max_iters);
+    let idx = if iters >= max_iters as usize {
+      palette_index(iters, max_iters, colour_map.len(), false)
+    } else if positive {
+      half + iters.min(half - 1)
+    } else {
+      iters.min(half - 1)
+    };
+    let this_colour = &colour_map[idx];
+
+    [this_colour[0], this_colour[1], this_colour[2]]
+  })
+}
+
+/***********************************************************************************************************************
+ * Draw a blend between the Mandelbrot Set and a Julia Set
+ * Each pixel is iterated as a Mandelbrot Set point c, but its orbit starts at blend * mouse_loc rather than the
+ * origin.  A blend of 0 gives the Mandelbrot Set, and increasing the blend towards 1 progressively distorts it into a
+ * Julia-like shape
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_morph(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  mouse_loc: Point,        // Mouse pointer coords on Mandelbrot set
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+  blend: f64,              // Fraction of mouse_loc used as the starting value of z
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let params = EscapeParams::new(max_iters);
+  let start_val = Point {
+    x: blend * mouse_loc.x,
+    y: blend * mouse_loc.y,
+  };
+
+  draw_pixels(ctx, &canvas, &axes_ranges, is_little_endian, |this_coord| {
+    let iters = escape_time_mj(&this_coord, start_val, &params);
+    let this_colour = &colour_map[palette_index(iters, max_iters, colour_map.len(), false)];
+
+    [this_colour[0], this_colour[1], this_colour[2]]
+  })
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set with the Julia Set for mouse_loc drawn over it in a rectangular inset
+ * Both sets use the same colour map, and the two images are combined before anything is drawn on the canvas
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_mandel_with_julia_inset(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges for the Mandelbrot Set
+  mouse_loc: Point,        // Mouse pointer coords on Mandelbrot set
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+  inset_x: u32,            // Pixel location of the inset's top left corner
+  inset_y: u32,            // "
+  inset_w: u32,            // Width of the inset in pixels
+  inset_h: u32,            // Height of the inset in pixels
+  julia_axes: AxesRanges,  // Extent of axes ranges for the Julia Set
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  if inset_w == 0
+    || inset_h == 0
+    || inset_x.saturating_add(inset_w) > canvas.width
+    || inset_y.saturating_add(inset_h) > canvas.height
+  {
+    return Err(FractalError::InvalidDimensions(
+      "inset must be non-empty and lie within the canvas".to_string(),
+    ));
+  }
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let inset = Region {
+    x: inset_x,
+    y: inset_y,
+    width: inset_w,
+    height: inset_h,
+  };
+
+  let render = |canvas: &Dimensions, axes_ranges: &AxesRanges, f_type: FractalType| {
+    render_fractal(
+      canvas,
+      axes_ranges,
+      EscapeParams::new(max_iters),
+      &colour_map,
+      f_type,
+      &RenderOptions::new(is_little_endian),
+    )
+  };
+
+  let mut image_data = render(&canvas, &axes_ranges, FractalType::Mandelbrot);
+  let inset_canvas = Dimensions {
+    width: inset_w,
+    height: inset_h,
+  };
+  let inset_data = render(&inset_canvas, &julia_axes, FractalType::Julia(mouse_loc));
+
+  copy_region(&mut image_data, &canvas, &inset_data, &inset);
+  put_image(ctx, &image_data, &canvas)
+}
+
+/***********************************************************************************************************************
+ * Draw a grid of small Julia Sets, each with a different value of c
+ * The cell in row r and column c uses the value c_start + c * c_step.x + i r * c_step.y, so the grid shows how the Julia
+ * Set changes as c moves across the parameter plane.  Every cell shows the same extent of the complex plane
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_julia_grid(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,     // Canvas dimensions
+  rows: u32,              // Number of rows of Julia Sets
+  cols: u32,              // Number of columns of Julia Sets
+  c_start: Point,         // Value of c used by the top left cell
+  c_step: Point,          // Change in c from one column (x) or row (y) to the next
+  julia_axes: AxesRanges, // Extent of axes ranges in each cell
+  max_iters: u32,         // Stop after this many iterations
+  c_map: JsValue,         // Selected colour map
+  is_little_endian: bool, // Is the processor little endian?
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  if rows == 0 || cols == 0 || rows > canvas.height || cols > canvas.width {
+    return Err(FractalError::InvalidDimensions(
+      "grid must have at least one cell, and no more cells than pixels".to_string(),
+    ));
+  }
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let grid = Dimensions {
+    width: cols,
+    height: rows,
+  };
+  let image_data = render_julia_grid(
+    &canvas,
+    &grid,
+    &julia_axes,
+    max_iters,
+    &colour_map,
+    is_little_endian,
+    |col, row| Point {
+      x: c_start.x + col as f64 * c_step.x,
+      y: c_start.y + row as f64 * c_step.y,
+    },
+  );
+
+  put_image(ctx, &image_data, &canvas)
+}
+
+// When the canvas does not divide exactly into cells, the cell boundaries are rounded down to whole pixels
+fn render_julia_grid<F>(
+  canvas: &Dimensions,
+  grid: &Dimensions, // Number of columns (width) and rows (height) of Julia Sets
+  julia_axes: &AxesRanges,
+  max_iters: u32,
+  colour_map: &[Vec<u32>],
+  is_little_endian: bool,
+  cell_c: F, // Returns the value of c used by the cell in the given column and row
+) -> Vec<u8>
+where
+  F: Fn(u32, u32) -> Point,
+{
+  let (cols, rows) = (grid.width, grid.height);
+  let mut image_data = vec![0u8; (canvas.width * canvas.height * 4) as usize];
+  let edge = |idx: u32, count: u32, len: u32| (idx as u64 * len as u64 / count as u64) as u32;
+
+  for row in 0..rows {
+    for col in 0..cols {
+      let (x, y) = (
+        edge(col, cols, canvas.width),
+        edge(row, rows, canvas.height),
+      );
+      let cell = Region {
+        x,
+        y,
+        width: edge(col + 1, cols, canvas.width) - x,
+        height: edge(row + 1, rows, canvas.height) - y,
+      };
+      let c = cell_c(col, row);
+      let cell_data = render_fractal(
+        &Dimensions {
+          width: cell.width,
+          height: cell.height,
+        },
+        julia_axes,
+        EscapeParams::new(max_iters),
+        colour_map,
+        FractalType::Julia(c),
+        &RenderOptions::new(is_little_endian),
+      );
+
+      copy_region(&mut image_data, canvas, &cell_data, &cell);
+    }
+  }
+
+  image_data
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set calculated at a resolution independent of the canvas size
+ * The axes ranges are sampled on a grid of x_samples x y_samples points, and the result is stretched to fill the
+ * canvas.  When the ratio of the sample counts differs from that of the canvas, each pixel of the calculated image
+ * covers a non-square area of the canvas, as needed for anamorphic output
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_mandel_resampled(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+  x_samples: u32,          // Number of points calculated along the X axis
+  y_samples: u32,          // Number of points calculated along the Y axis
+) -> Result<(), FractalError> {
+  let samples = Dimensions {
+    width: x_samples,
+    height: y_samples,
+  };
+  validate_dimensions(&canvas)?;
+  validate_dimensions(&samples)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let image_data = render_fractal(
+    &samples,
+    &axes_ranges,
+    EscapeParams::new(max_iters),
+    &colour_map,
+    FractalType::Mandelbrot,
+    &RenderOptions::new(is_little_endian),
+  );
+
+  put_image(ctx, &stretch_image(&image_data, &samples, &canvas), &canvas)
+}
+
+/***********************************************************************************************************************
+ * Draw a four-fold symmetrical image from a Mandelbrot Set
+ * Only the top left quarter of the axes ranges is calculated, and this is reflected left to right and top to bottom to
+ * fill the canvas
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_kaleidoscope(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let params = EscapeParams::new(max_iters);
+  let iter_buffer = map_quadrant_pixels(&canvas, &axes_ranges, |this_coord| {
+    mandel_iter(&this_coord, &params)
+  });
+
+  let image_data = render_buffer(&iter_buffer, is_little_endian, |&iters| {
+    let this_colour = &colour_map[palette_index(iters, max_iters, colour_map.len(), false)];
+    [this_colour[0], this_colour[1], this_colour[2]]
+  });
+
+  put_image(ctx, &image_data, &canvas)
+}
+
+/***********************************************************************************************************************
+ * Calculate a Mandelbrot Set and write its RGBA byte data into a buffer supplied by the caller
+ * Nothing is drawn, so the caller can build an ImageData object from the same buffer on every frame instead of having
+ * a new one allocated for each render.  The buffer must hold exactly width * height * 4 bytes.  A typed array passed
+ * from JavaScript is copied into WebAssembly memory and back again, so no ImageData or Vec is created, but the bytes are
+ * still copied
+ */
+#[wasm_bindgen]
+pub fn render_mandel_into(
+  buffer: &mut [u8],       // RGBA byte data written by this function
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let byte_count = canvas.width as usize * canvas.height as usize * 4;
+
+  if buffer.len() != byte_count {
+    return Err(FractalError::InvalidDimensions(format!(
+      "buffer holds {} bytes, but a {} x {} canvas needs {}",
+      buffer.len(),
+      canvas.width,
+      canvas.height,
+      byte_count
+    )));
+  }
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let params = EscapeParams::new(max_iters);
+
+  render_pixels_into(
+    buffer,
+    &canvas,
+    &axes_ranges,
+    is_little_endian,
+    false,
+    |this_coord| {
+      let iters = mandel_iter(&this_coord, &params);
+      let this_colour = &colour_map[palette_index(iters, max_iters, colour_map.len(), false)];
+
+      [this_colour[0], this_colour[1], this_colour[2]]
+    },
+  );
+
+  Ok(())
+}
+
+/***********************************************************************************************************************
+ * Fill a canvas with a preview of a colour map
+ */
+#[wasm_bindgen]
+pub fn draw_palette_strip(
+  ctx: &CanvasRenderingContext2d,
+  width: u32,             // Canvas width
+  height: u32,            // Canvas height
+  c_map: JsValue,         // Selected colour map
+  is_little_endian: bool, // Is the processor little endian?
+) -> Result<(), FractalError> {
+  let canvas = Dimensions { width, height };
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let image_data = render_palette_strip(&canvas, &colour_map, is_little_endian);
+
+  put_image(ctx, &image_data, &canvas)
+}
+
+/***********************************************************************************************************************
+ * Return the Mandelbrot Set iteration value of a single pixel
+ * The pixel is scaled to the fractal's coordinate space in exactly the same way as when the whole canvas is drawn
+ */
+#[wasm_bindgen]
+pub fn iter_at_pixel(
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  px: u32,                 // Pixel location on the canvas
+  py: u32,                 // "
+  max_iters: u32,          // Stop after this many iterations
+) -> Result<u32, FractalError> {
+  validate_dimensions(&canvas)?;
+
+  if px >= canvas.width || py >= canvas.height {
+    return Err(FractalError::InvalidParameter(format!(
+      "pixel ({}, {}) lies outside the {} x {} canvas",
+      px, py, canvas.width, canvas.height
+    )));
+  }
+
+  let this_coord = pixel_to_point(&canvas, &axes_ranges)(px, py);
+  Ok(mandel_iter(&this_coord, &EscapeParams::new(max_iters)) as u32)
+}
+
+/***********************************************************************************************************************
+ * Return the Mandelbrot Set iteration value of every point in a list of points flattened into [x0, y0, x1, y1, ...]
+ * If the list has an odd length, the last value is ignored
+ */
+#[wasm_bindgen]
+pub fn escape_times_batch(points: &[f64], max_iters: u32) -> Vec<u32> {
+  let params = EscapeParams::new(max_iters);
+
+  points
+    .chunks_exact(2)
+    .map(|xy| mandel_iter(&Point { x: xy[0], y: xy[1] }, &params) as u32)
+    .collect()
+}
+
+/***********************************************************************************************************************
+ * Draw a Buddhabrot: the density of the orbits of points that escape from the Mandelbrot Set
+ * The square root of each pixel's hit count relative to the highest hit count is spread across the colour map.  The
+ * more samples taken, the smoother the image
+ */
+#[wasm_bindgen]
+pub fn draw_buddhabrot(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  samples: u32,            // Number of random points to sample
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+  let density = buddhabrot_density(&canvas, &axes_ranges, samples, &max_iters);
+  let max_density = density.iter().copied().max().unwrap_or(0).max(1) as f64;
+  let last = (colour_map.len() - 1) as f64;
+
+  let image_data = render_buffer(&density, is_little_endian, |&hits| {
+    let brightness = (hits as f64 / max_density).sqrt();
+    interpolate_colour(&colour_map, brightness * last, max_iters, false)
+  });
+
+  put_image(ctx, &image_data, &canvas)
+}
+
+/***********************************************************************************************************************
+ * Draw the Nova fractal z = z - R(z^3 - 1)/3z^2 + c
+ * As with the Newton fractal, the first three colour map entries are the colours of the three roots of z^3 - 1.  Each
+ * pixel uses the colour of the root nearest to where its orbit converges, shaded by the number of iterations needed
+ */
+#[allow(clippy::too_many_arguments)]
+#[wasm_bindgen]
+pub fn draw_nova(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  c_map: JsValue,          // Selected colour map
+  is_little_endian: bool,  // Is the processor little endian?
+  relaxation_x: f64,       // Real part of the relaxation parameter R
+  relaxation_y: f64,       // Imaginary part of the relaxation parameter R
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let colour_map = parse_colour_map(&c_map)?;
+
+  if colour_map.len() < NEWTON_ROOTS.len() {
+    return Err(FractalError::InvalidPalette(
+      "the Nova fractal needs one colour per root".to_string(),
+    ));
+  }
+
+  let relaxation = Complex::new(relaxation_x, relaxation_y);
+
+  draw_pixels(ctx, &canvas, &axes_ranges, is_little_endian, |this_coord| {
+    let (root_idx, iters) = nova_iter(&this_coord, &relaxation, &max_iters);
+    newton_colour(&colour_map, root_idx, iters, max_iters)
+  })
+}
+
+/***********************************************************************************************************************
+ * Return the (possibly fractional) pixel location on the canvas of a point in the fractal's coordinate space
+ * This is the inverse of the scaling used when the fractal is drawn, so it can be used to place markers over the image
+ * A canvas less than 2 pixels wide or high is scaled as if it were 2 pixels, so the result is always finite
+ */
+#[wasm_bindgen]
+pub fn coord_to_pixel(
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  p: Point,                // Point in the fractal's coordinate space
+) -> Point {
+  let (x, y) = point_to_pixel(&canvas, &axes_ranges)(p.x, p.y);
+  Point { x, y }
+}
+
+/***********************************************************************************************************************
+ * Return the point in the fractal's coordinate space at a (possibly fractional) pixel location on the canvas
+ * This uses exactly the same scaling as when the fractal is drawn, so it gives the coordinates under the mouse pointer
+ * As with coord_to_pixel, a canvas less than 2 pixels wide or high is scaled as if it were 2 pixels
+ */
+#[wasm_bindgen]
+pub fn pixel_to_coord(
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  px: f64,                 // Pixel location on the canvas
+  py: f64,                 // "
+) -> Point {
+  fractional_pixel_to_point(&canvas, &axes_ranges)(px, py)
+}
+
+/***********************************************************************************************************************
+ * Draw a Mandelbrot Set in grayscale, ignoring any colour map
+ * The brightness of each pixel is iters / max_iters * 255, so the image can be used as a heightmap in which points
+ * inside the set are white
+ */
+#[wasm_bindgen]
+pub fn draw_mandel_grayscale(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  max_iters: u32,          // Stop after this many iterations
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  let params = EscapeParams::new(max_iters);
+
+  draw_pixels(ctx, &canvas, &axes_ranges, is_little_endian, |this_coord| {
+    let level = grayscale_level(mandel_iter(&this_coord, &params), max_iters);
+
+    [level, level, level]
+  })
+}
+
+fn grayscale_level(iters: usize, max_iters: u32) -> u32 {
+  if max_iters == 0 {
+    255
+  } else {
+    (iters.min(max_iters as usize) as f64 / max_iters as f64 * 255.0).round() as u32
+  }
+}
+
+/***********************************************************************************************************************
+ * Find the nucleus of a minibrot or bulb close to the point x + i y
+ * Sample points within the search radius that lie inside the set are iterated until their orbit settles on an attracting
+ * cycle, then the period of that cycle is used to refine the sample point into the nucleus of its component.  The
+ * nucleus with the lowest period is returned, since that belongs to the largest component, with ties going to the
+ * nucleus closest to x + i y.  If no nucleus is found, x + i y is returned unchanged
+ */
+#[wasm_bindgen]
+pub fn find_minibrot_near(
+  x: f64,             // Real part of the search centre
+  y: f64,             // Imaginary part of the search centre
+  search_radius: f64, // Only nuclei within this distance of the search centre are returned
+  max_iters: u32,     // Iterations used both to settle each orbit and to search for its period
+) -> Point {
+  let radius_sq = search_radius * search_radius;
+  let mut best: Option<(u32, f64, Complex)> = None;
+
+  for row in -MINIBROT_SEARCH_STEPS..=MINIBROT_SEARCH_STEPS {
+    for col in -MINIBROT_SEARCH_STEPS..=MINIBROT_SEARCH_STEPS {
+      let offset_x = search_radius * col as f64 / MINIBROT_SEARCH_STEPS as f64;
+      let offset_y = search_radius * row as f64 / MINIBROT_SEARCH_STEPS as f64;
+
+      if sum_of_squares(offset_x, offset_y) > radius_sq {
+        continue;
+      }
+
+      let sample = Complex::new(x + offset_x, y + offset_y);
+      let candidate = attracting_period(sample, max_iters)
+        .and_then(|period| find_nucleus(sample, period).map(|nucleus| (period, nucleus)));
+
+      if let Some((period, nucleus)) = candidate {
+        let dist_sq = sum_of_squares(nucleus.re - x, nucleus.im - y);
+        let is_better = match best {
+          Some((best_period, best_dist_sq, _)) => (period, dist_sq) < (best_period, best_dist_sq),
+          None => true,
+        };
+
+        if dist_sq <= radius_sq && is_better {
+          best = Some((period, dist_sq, nucleus));
+        }
+      }
+    }
+  }
+
+  match best {
+    Some((_, _, nucleus)) => Point {
+      x: nucleus.re,
+      y: nucleus.im,
+    },
+    None => Point { x, y },
+  }
+}
+
+// Iterate z = z^2 + c for max_iters steps to let the orbit settle, then return the length of the cycle it has settled
+// on, or None if the orbit escapes or is not yet periodic
+fn attracting_period(c: Complex, max_iters: u32) -> Option<u32> {
+  let mut z = Complex::new(0.0, 0.0);
+
+  for _ in 0..max_iters {
+    z = z.sqr().add(c);
+
+    if z.abs_sq() > BAILOUT {
+      return None;
+    }
+  }
+
+  let settled = z;
+
+  for period in 1..=max_iters {
+    z = z.sqr().add(c);
+
+    if sum_of_squares(z.re - settled.re, z.im - settled.im) < PERIODICITY_EPSILON {
+      return Some(period);
+    }
+  }
+
+  None
+}
+
+// The nucleus of a component of period p is a root of f_c^p(0) = 0, which Newton's method finds using the derivative
+// with respect to c: dz_(n+1) = 2 z_n dz_n + 1
+fn find_nucleus(start: Complex, period: u32) -> Option<Complex> {
+  let mut c = start;
+
+  for _ in 0..MINIBROT_NEWTON_STEPS {
+    let mut z = Complex::new(0.0, 0.0);
+    let mut dz = Complex::new(0.0, 0.0);
+
+    for _ in 0..period {
+      dz = Complex::new(2.0, 0.0)
+        .mul(z)
+        .mul(dz)
+        .add(Complex::new(1.0, 0.0));
+      z = z.sqr().add(c);
+    }
+
+    if dz.abs_sq() == 0.0 {
+      return None;
+    }
+
+    let step = z.div(dz);
+    c = c.sub(step);
+
+    if !(c.re.is_finite() && c.im.is_finite()) {
+      return None;
+    }
+
+    if step.abs_sq() < NEWTON_EPSILON * NEWTON_EPSILON {
+      return Some(c);
+    }
+  }
+
+  None
+}
+
+/***********************************************************************************************************************
+ * Estimate the area of the Mandelbrot Set
+ * The centre of each cell in a sample_grid x sample_grid grid covering the set's bounding box is tested for membership,
+ * and the fraction of cells inside the set is scaled by the area of the box.  The estimate converges on about 1.5066
+ * as both the grid size and the iteration limit increase
+ */
+#[wasm_bindgen]
+pub fn estimate_mandelbrot_area(sample_grid: u32, max_iters: u32) -> f64 {
+  if sample_grid == 0 {
+    return 0.0;
+  }
+
+  let params = EscapeParams::new(max_iters);
+  let cell_width = (MANDEL_BOX_X.1 - MANDEL_BOX_X.0) / sample_grid as f64;
+  let cell_height = (MANDEL_BOX_Y.1 - MANDEL_BOX_Y.0) / sample_grid as f64;
+  let mut inside_count: u64 = 0;
+
+  for row in 0..sample_grid {
+    for col in 0..sample_grid {
+      let sample = Point {
+        x: MANDEL_BOX_X.0 + (col as f64 + 0.5) * cell_width,
+        y: MANDEL_BOX_Y.0 + (row as f64 + 0.5) * cell_height,
+      };
+
+      if mandel_iter(&sample, &params) >= max_iters as usize {
+        inside_count += 1;
+      }
+    }
+  }
+
+  inside_count as f64 * cell_width * cell_height
+}
+
+/***********************************************************************************************************************
+ * Draw a checkerboard whose squares are aligned to integer coordinates, for checking the mapping from pixels to points
+ * A point is white if floor(x) + floor(y) is even, else black, so the origin is always the bottom left corner of a
+ * white square
+ */
+#[wasm_bindgen]
+pub fn draw_test_pattern(
+  ctx: &CanvasRenderingContext2d,
+  canvas: Dimensions,      // Canvas dimensions
+  axes_ranges: AxesRanges, // Extent of axes ranges
+  is_little_endian: bool,  // Is the processor little endian?
+) -> Result<(), FractalError> {
+  validate_dimensions(&canvas)?;
+
+  draw_pixels(ctx, &canvas, &axes_ranges, is_little_endian, |this_coord| {
+    test_pattern_colour(&this_coord)
+  })
+}
+
+fn test_pattern_colour(loc: &Point) -> [u32; 3] {
+  if (loc.x.floor() + loc.y.floor()).rem_euclid(2.0) == 0.0 {
+    [0xff, 0xff, 0xff]
+  } else {
+    [0, 0, 0]
+  }
+}
+
+/***********************************************************************************************************************
+ * Return the iteration value of the point c_x + i c_y in the Mandelbrot Set
+ * This is a native-only entry point that gives tests a fixed reference to check the escape time calculation against
+ */
+#[cfg(not(target_arch = "wasm32"))]
+pub fn escape_time_at(c_x: f64, c_y: f64, max_iters: u32) -> usize {
+  mandel_iter(&Point { x: c_x, y: c_y }, &EscapeParams::new(max_iters))
+}
+
+// *********************************************************************************************************************
+// TESTS
+// *********************************************************************************************************************
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const MAX_ITERS: u32 = 100;
+
+  #[test]
+  fn invalid_arguments_give_distinct_errors() {
+    assert!(matches!(
+      gen_struct_range(1.0, -2.0),
+      Err(FractalError::InvalidRange(_))
+    ));
+    assert!(matches!(
+      gen_struct_axes_ranges(-2.0, 1.0, f64::NAN, 1.0),
+      Err(FractalError::InvalidRange(_))
+    ));
+    assert!(matches!(
+      validate_dimensions(&Dimensions {
+        width: 0,
+        height: 10
+      }),
+      Err(FractalError::InvalidDimensions(_))
+    ));
+    assert!(matches!(
+      flat_palette_len(&[1, 2]),
+      Err(FractalError::InvalidPalette(_))
+    ));
+    assert!(matches!(
+      parse_lyapunov_sequence("ABC"),
+      Err(FractalError::InvalidParameter(_))
+    ));
+
+    assert_eq!(
+      FractalError::InvalidPalette("no colours supplied".to_string()).to_string(),
+      "Invalid colour map: no colours supplied"
+    );
+  }
+
+  #[test]
+  fn lyapunov_exponent_has_known_values() {
+    let constant_r = |r: f64| Point { x: r, y: r };
+
+    // x = 0.5 is a superstable fixed point when r = 2
+    assert_eq!(
+      lyapunov_exponent(&constant_r(2.0), &[true], &1000),
+      f64::NEG_INFINITY
+    );
+
+    // When r = 3.2 the orbit settles on a 2-cycle whose multiplier is 4 + 2r - r^2 = 0.16
+    let period_2 = lyapunov_exponent(&constant_r(3.2), &[true], &1000);
+    assert!((period_2 - 0.16f64.ln() / 2.0).abs() < 1e-6);
+
+    // r = 3.9 is chaotic
+    assert!(lyapunov_exponent(&constant_r(3.9), &[true], &1000) > 0.0);
+
+    // a and b are used in the order given by the sequence
+    let ab = Point { x: 3.2, y: 3.9 };
+    assert_ne!(
+      lyapunov_exponent(&ab, &[true, false], &1000),
+      lyapunov_exponent(&ab, &[false, true], &1000)
+    );
+  }
+
+  #[test]
+  fn range_arguments_are_min_then_max() {
+    let range = gen_struct_range(-2.0, 1.0).unwrap();
+    assert_eq!((range.min, range.max), (-2.0, 1.0));
+
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 1.0, -1.5, 1.5).unwrap();
+    assert_eq!(
+      (axes_ranges.x_range.min, axes_ranges.x_range.max),
+      (-2.0, 1.0)
+    );
+    assert_eq!(
+      (axes_ranges.y_range.min, axes_ranges.y_range.max),
+      (-1.5, 1.5)
+    );
+  }
+
+  #[test]
+  fn origin_is_interior() {
+    assert_eq!(escape_time_at(0.0, 0.0, MAX_ITERS), MAX_ITERS as usize);
+  }
+
+  #[test]
+  fn minus_one_is_interior() {
+    assert_eq!(escape_time_at(-1.0, 0.0, MAX_ITERS), MAX_ITERS as usize);
+  }
+
+  // After one step z = 2 lies exactly on the bailout circle |z|^2 = 4, which does not count as having escaped, so the
+  // point escapes on the following step
+  #[test]
+  fn two_escapes_after_second_step() {
+    assert_eq!(escape_time_at(2.0, 0.0, MAX_ITERS), 2);
+  }
+
+  #[test]
+  fn point_beyond_bailout_escapes_after_first_step() {
+    assert_eq!(escape_time_at(2.5, 0.0, MAX_ITERS), 1);
+  }
+
+  #[test]
+  fn boundary_point_straddles_the_boundary() {
+    // Seeds both outside and inside the set
+    for &(near_x, near_y, radius) in [(-0.75, 0.3, 0.2), (0.0, 0.0, 1.0)].iter() {
+      let found = find_boundary_point(near_x, near_y, radius, MAX_ITERS);
+
+      assert!(sum_of_squares(found.x - near_x, found.y - near_y) <= radius * radius);
+
+      // Sample a small circle around the point found
+      let interior_samples = (0..16)
+        .map(|idx| {
+          let angle = idx as f64 * std::f64::consts::TAU / 16.0;
+          let probe = radius * 0.01;
+          escape_time_at(
+            found.x + probe * angle.cos(),
+            found.y + probe * angle.sin(),
+            MAX_ITERS,
+          )
+        })
+        .filter(|&iters| iters >= MAX_ITERS as usize)
+        .count();
+
+      assert!(interior_samples > 0 && interior_samples < 16);
+    }
+  }
+
+  #[test]
+  fn resampled_palette_keeps_end_colours() {
+    let colour_map = vec![vec![0, 0, 0], vec![100, 50, 200], vec![255, 255, 255]];
+    let resampled = resample_colour_map(&colour_map, 9);
+
+    assert_eq!(resampled.len(), 9);
+    assert_eq!(resampled[0], colour_map[0]);
+    assert_eq!(resampled[4], colour_map[1]);
+    assert_eq!(resampled[8], colour_map[2]);
+    assert_eq!(resampled[2], vec![50, 25, 100]);
+  }
+
+  #[test]
+  fn mask_makes_excluded_pixels_transparent() {
+    let mut image_data = vec![0xff; 8];
+    apply_mask(
+      &mut image_data,
+      [false, true].iter().copied(),
+      RenderMask::ExteriorOnly,
+      true,
+    );
+    assert_eq!(
+      image_data,
+      vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0]
+    );
+
+    let mut image_data = vec![0xff; 8];
+    apply_mask(
+      &mut image_data,
+      [false, true].iter().copied(),
+      RenderMask::InteriorOnly,
+      false,
+    );
+    assert_eq!(
+      image_data,
+      vec![0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+    );
+  }
+
+  #[test]
+  fn interior_colour_replaces_last_palette_entry() {
+    let canvas = Dimensions {
+      width: 5,
+      height: 3,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.0, -1.0, 1.0).unwrap();
+    let colour_map = vec![vec![10, 20, 30], vec![40, 50, 60]];
+
+    for &smooth in [false, true].iter() {
+      let image_data = render_fractal(
+        &canvas,
+        &axes_ranges,
+        EscapeParams::new(MAX_ITERS),
+        &colour_map,
+        FractalType::Mandelbrot,
+        &RenderOptions {
+          smooth,
+          interior_colour: Some([0, 0, 128]),
+          ..RenderOptions::new(true)
+        },
+      );
+
+      // The centre of the canvas is -1 + 0i, which is inside the set, and the top left corner -2 - i is outside
+      assert_eq!(image_data[28..32], [0, 0, 128, 0xff]);
+      assert_ne!(image_data[0..4], [0, 0, 128, 0xff]);
+    }
+  }
+
+  #[test]
+  fn points_snap_to_nearest_grid_line() {
+    let snapped = snap_to_grid(
+      Point {
+        x: -0.7512,
+        y: 0.0987,
+      },
+      0.05,
+    );
+
+    assert!((snapped.x + 0.75).abs() < 1e-12);
+    assert!((snapped.y - 0.1).abs() < 1e-12);
+  }
+
+  #[test]
+  fn gradient_stops_are_sorted_and_interpolated() {
+    let stop = |pos, r, g, b| GradientStop { pos, r, g, b };
+    let mut stops = vec![
+      stop(1.0, 255, 255, 255),
+      stop(0.0, 0, 0, 0),
+      stop(0.5, 200, 100, 0),
+    ];
+    let colour_map = gradient_colour_map(&mut stops, 5);
+
+    assert_eq!(
+      colour_map,
+      vec![
+        vec![0, 0, 0],
+        vec![100, 50, 0],
+        vec![200, 100, 0],
+        vec![228, 178, 128],
+        vec![255, 255, 255],
+      ]
+    );
+  }
+
+  #[test]
+  fn panned_buffer_matches_full_recalculation() {
+    let canvas = Dimensions {
+      width: 41,
+      height: 33,
+    };
+    let params = EscapeParams::new(MAX_ITERS);
+    let pixel_value = |this_coord: Point| mandel_iter(&this_coord, &params);
+
+    // Each pixel is 1/16 wide and high, so every pixel location is exact and the new ranges are 3 pixels right and 2
+    // pixels up
+    let old_axes = gen_struct_axes_ranges(-2.0, 0.5, -1.0, 1.0).unwrap();
+    let new_axes = gen_struct_axes_ranges(-1.8125, 0.6875, -0.875, 1.125).unwrap();
+    let old_buffer = map_pixels(&canvas, &old_axes, pixel_value);
+
+    assert_eq!(
+      shift_iter_buffer(&canvas, &old_axes, &old_buffer, &new_axes, pixel_value),
+      map_pixels(&canvas, &new_axes, pixel_value)
+    );
+  }
+
+  #[test]
+  fn perturbation_matches_direct_calculation() {
+    let canvas = Dimensions {
+      width: 41,
+      height: 33,
+    };
+    let params = EscapeParams::new(MAX_ITERS);
+
+    for axes_ranges in [
+      gen_struct_axes_ranges(-2.0, 0.5, -1.0, 1.0).unwrap(),
+      gen_struct_axes_ranges(-0.76, -0.74, 0.09, 0.11).unwrap(),
+    ]
+    .iter()
+    {
+      let direct = map_pixels(&canvas, axes_ranges, |this_coord| {
+        mandel_iter(&this_coord, &params)
+      });
+      let perturbed = compute_perturbation_buffer(&canvas, axes_ranges, &params);
+
+      // Rounding errors differ between the two methods, so a handful of chaotic pixels are allowed to disagree
+      let matching = direct
+        .iter()
+        .zip(perturbed.iter())
+        .filter(|(a, b)| a == b)
+        .count();
+      assert!(matching * 100 >= direct.len() * 98);
+    }
+  }
+
+  #[test]
+  fn perturbation_handles_reference_that_escapes_immediately() {
+    let canvas = Dimensions {
+      width: 9,
+      height: 7,
+    };
+    let axes_ranges = gen_struct_axes_ranges(2.9, 3.1, -0.1, 0.1).unwrap();
+    let params = EscapeParams::new(MAX_ITERS);
+    let direct = map_pixels(&canvas, &axes_ranges, |this_coord| {
+      mandel_iter(&this_coord, &params)
+    });
+
+    assert_eq!(
+      compute_perturbation_buffer(&canvas, &axes_ranges, &params),
+      direct
+    );
+  }
+
+  #[test]
+  fn pixel_iteration_matches_buffer() {
+    let canvas = Dimensions {
+      width: 40,
+      height: 30,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.5, -1.2, 1.2).unwrap();
+    let buffer = compute_mandel_iters(canvas, axes_ranges, MAX_ITERS).unwrap();
+
+    for &(px, py) in [(0, 0), (20, 15), (31, 7), (39, 29)].iter() {
+      assert_eq!(
+        iter_at_pixel(canvas, axes_ranges, px, py, MAX_ITERS).unwrap(),
+        buffer[(py * canvas.width + px) as usize]
+      );
+    }
+
+    assert!(matches!(
+      iter_at_pixel(canvas, axes_ranges, 40, 0, MAX_ITERS),
+      Err(FractalError::InvalidParameter(_))
+    ));
+    assert!(matches!(
+      iter_at_pixel(canvas, axes_ranges, 0, 30, MAX_ITERS),
+      Err(FractalError::InvalidParameter(_))
+    ));
+    assert!(matches!(
+      iter_at_pixel(
+        Dimensions {
+          width: 0,
+          height: 30
+        },
+        axes_ranges,
+        0,
+        0,
+        MAX_ITERS
+      ),
+      Err(FractalError::InvalidDimensions(_))
+    ));
+  }
+
+  #[test]
+  fn palette_offset_rotates_colours() {
+    let colour_map = vec![vec![0, 0, 0], vec![100, 50, 0], vec![200, 100, 0]];
+
+    for iters in 0..5 {
+      let idx = palette_index(iters, MAX_ITERS, colour_map.len(), false);
+      let this_colour = &colour_map[idx];
+      assert_eq!(
+        cycled_colour(&colour_map, iters, MAX_ITERS, 0.0),
+        [this_colour[0], this_colour[1], this_colour[2]]
+      );
+    }
+
+    assert_eq!(cycled_colour(&colour_map, 0, MAX_ITERS, 0.5), [50, 25, 0]);
+    assert_eq!(cycled_colour(&colour_map, 2, MAX_ITERS, 0.5), [100, 50, 0]);
+    assert_eq!(cycled_colour(&colour_map, 1, MAX_ITERS, 3.0), [100, 50, 0]);
+    assert_eq!(
+      cycled_colour(&colour_map, 0, MAX_ITERS, -1.0),
+      [200, 100, 0]
+    );
+    assert_eq!(
+      cycled_colour(&colour_map, MAX_ITERS as usize, MAX_ITERS, 0.5),
+      [200, 100, 0]
+    );
+  }
+
+  #[test]
+  fn smooth_julia_values_track_integer_counts() {
+    let mouse_loc = Point { x: -0.8, y: 0.156 };
+    let params = EscapeParams::new(MAX_ITERS);
+
+    for &(x, y) in [(1.5, 0.0), (0.9, 0.6), (-0.3, 1.1), (0.0, 0.0)].iter() {
+      let loc = Point { x, y };
+      let iters = fractal_iter(&FractalType::Julia(mouse_loc), loc, &params) as f64;
+      let smooth = smooth_fractal_iter(&FractalType::Julia(mouse_loc), loc, &params);
+
+      assert!(
+        (smooth - iters).abs() <= 1.0,
+        "{} vs {} at ({}, {})",
+        smooth,
+        iters,
+        x,
+        y
+      );
+    }
+  }
+
+  #[test]
+  fn subdivision_matches_brute_force_outside_solid_rectangles() {
+    let canvas = Dimensions {
+      width: 64,
+      height: 48,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.5, -1.2, 1.2).unwrap();
+    let params = EscapeParams::new(MAX_ITERS);
+    let direct = compute_mandel_iters(canvas, axes_ranges, MAX_ITERS).unwrap();
+    let subdivided = compute_subdivided_buffer(&canvas, &axes_ranges, &params);
+
+    assert_eq!(subdivided.len(), direct.len());
+    assert!(subdivided.iter().all(|&iters| iters <= MAX_ITERS as usize));
+
+    let matching = direct
+      .iter()
+      .zip(subdivided.iter())
+      .filter(|(&direct, &subdivided)| direct as usize == subdivided)
+      .count();
+    assert!(matching * 100 >= direct.len() * 99);
+  }
+
+  #[test]
+  fn final_angle_is_normalised() {
+    assert_eq!(
+      mandel_final_angle(&Point { x: -0.5, y: 0.0 }, &MAX_ITERS),
+      None
+    );
+
+    // c = 3 escapes after one step with z = 3, and c = -3 escapes with z = -3
+    assert_eq!(
+      mandel_final_angle(&Point { x: 3.0, y: 0.0 }, &MAX_ITERS),
+      Some(0.0)
+    );
+    assert_eq!(
+      mandel_final_angle(&Point { x: -3.0, y: 0.0 }, &MAX_ITERS),
+      Some(std::f64::consts::PI)
+    );
+
+    let angle = mandel_final_angle(&Point { x: 0.3, y: -2.5 }, &MAX_ITERS).unwrap();
+    assert!((0.0..2.0 * std::f64::consts::PI).contains(&angle));
+  }
+
+  #[test]
+  fn smooth_f32_interior_is_max_iters() {
+    let canvas = Dimensions {
+      width: 40,
+      height: 30,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.5, -1.2, 1.2).unwrap();
+    let smooth = compute_mandel_smooth_f32(canvas, axes_ranges, MAX_ITERS).unwrap();
+    let iters = compute_mandel_iters(canvas, axes_ranges, MAX_ITERS).unwrap();
+
+    for (&value, &iters) in smooth.iter().zip(iters.iter()) {
+      assert_eq!(value == MAX_ITERS as f32, iters == MAX_ITERS);
+    }
+  }
+
+  #[test]
+  fn julia_bounding_box_contains_the_set() {
+    // The Julia Set for c = 0 is the unit disc
+    let axes_ranges = julia_bounding_box(Point { x: 0.0, y: 0.0 }, 81, MAX_ITERS);
+
+    for range in [axes_ranges.x_range, axes_ranges.y_range].iter() {
+      assert!(range.min < -1.0 && range.min > -1.3);
+      assert!(range.max > 1.0 && range.max < 1.3);
+    }
+  }
+
+  #[test]
+  fn boundary_refinement_only_changes_edge_pixels() {
+    let canvas = Dimensions {
+      width: 60,
+      height: 45,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.5, -1.2, 1.2).unwrap();
+    let params = EscapeParams::new(20);
+    let direct = compute_fractal_buffer(&canvas, &axes_ranges, &params, &FractalType::Mandelbrot);
+    let mut refined = direct.clone();
+
+    refine_boundary_pixels(
+      &canvas,
+      &axes_ranges,
+      &params,
+      &FractalType::Mandelbrot,
+      &mut refined,
+    );
+
+    let changed: Vec<usize> = (0..direct.len())
+      .filter(|&idx| direct[idx] != refined[idx])
+      .collect();
+    assert!(!changed.is_empty());
+
+    let width = canvas.width as usize;
+
+    for idx in changed {
+      assert_eq!((direct[idx], refined[idx]), (20, 19));
+
+      // A refined pixel escaped later than anything outside the set next to it
+      let (ix, iy) = (idx % width, idx / width);
+      let neighbours = [
+        (ix > 0).then(|| idx - 1),
+        (ix + 1 < width).then(|| idx + 1),
+        (iy > 0).then(|| idx - width),
+        (idx + width < refined.len()).then(|| idx + width),
+      ];
+
+      for &neighbour in neighbours.iter().flatten() {
+        if direct[neighbour] < 20 {
+          assert!(refined[idx] >= refined[neighbour]);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn render_config_setters_chain() {
+    let config = RenderConfig::new()
+      .with_bailout(1.0)
+      .with_power(5)
+      .with_alpha(0x80)
+      .with_smoothing(true)
+      .with_interior_color(vec![0, 0, 64])
+      .unwrap()
+      .with_dither(true)
+      .with_refine_boundary(true);
+
+    assert_eq!(config.bailout, BAILOUT);
+    assert_eq!((config.power, config.alpha, config.smooth), (5, 0x80, true));
+    assert_eq!(config.interior_colour, Some([0, 0, 64]));
+    assert!(config.dither && config.refine_boundary);
+    assert_eq!(RenderConfig::new().with_bailout(16.0).bailout, 16.0);
+  }
+
+  #[test]
+  fn coord_to_pixel_inverts_pixel_scaling() {
+    let canvas = Dimensions {
+      width: 41,
+      height: 33,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.5, -1.2, 1.2).unwrap();
+    let to_coord = pixel_to_point(&canvas, &axes_ranges);
+
+    for &(px, py) in [(0, 0), (20, 16), (40, 32), (7, 29)].iter() {
+      let this_coord = to_coord(px, py);
+      let pixel = coord_to_pixel(canvas, axes_ranges, this_coord);
+      let coord = pixel_to_coord(canvas, axes_ranges, px as f64, py as f64);
+
+      assert!((pixel.x - px as f64).abs() < 1e-9 && (pixel.y - py as f64).abs() < 1e-9);
+      assert_eq!((coord.x, coord.y), (this_coord.x, this_coord.y));
+    }
+
+    // Degenerate canvases give finite results rather than NaN, infinity or an arithmetic overflow
+    for &(width, height) in [(0, 0), (1, 1), (0, 33), (41, 1)].iter() {
+      let canvas = Dimensions { width, height };
+      let pixel = coord_to_pixel(canvas, axes_ranges, Point { x: 0.0, y: 0.0 });
+      let coord = pixel_to_coord(canvas, axes_ranges, 0.5, 0.5);
+
+      assert!(pixel.x.is_finite() && pixel.y.is_finite());
+      assert!(coord.x.is_finite() && coord.y.is_finite());
+    }
+  }
+
+  #[test]
+  fn binary_decomposition_reports_sign_of_escaped_z() {
+    assert_eq!(
+      mandel_binary_decomp(&Point { x: 0.0, y: 0.0 }, &MAX_ITERS),
+      (100, false)
+    );
+
+    // c = 0.5 + 2i escapes after one step with z = c, and c = 0.5 - 2i escapes with z = c
+    assert_eq!(
+      mandel_binary_decomp(&Point { x: 0.5, y: 2.0 }, &MAX_ITERS),
+      (1, true)
+    );
+    assert_eq!(
+      mandel_binary_decomp(&Point { x: 0.5, y: -2.0 }, &MAX_ITERS),
+      (1, false)
+    );
+  }
+
+  #[test]
+  fn periodicity_detection_reduces_steps() {
+    let params = EscapeParams {
+      detect_periodicity: true,
+      ..EscapeParams::new(MAX_ITERS)
+    };
+    let origin = Point { x: 0.0, y: 0.0 };
+    let (iters, _, steps) =
+      escape_time_with_steps(&Point { x: -0.1, y: 0.1 }, origin, &params, mj_step);
+
+    assert_eq!(iters, MAX_ITERS);
+    assert!(steps < MAX_ITERS);
+
+    let (iters, _, steps) =
+      escape_time_with_steps(&Point { x: 1.0, y: 1.0 }, origin, &params, mj_step);
+    assert_eq!(iters, steps);
+  }
+
+  #[test]
+  fn palette_strip_has_one_stripe_per_entry() {
+    let canvas = Dimensions {
+      width: 6,
+      height: 2,
+    };
+    let colour_map = vec![vec![255, 0, 0], vec![0, 255, 0], vec![0, 0, 255]];
+    let image_data = render_palette_strip(&canvas, &colour_map, true);
+
+    let red = [255, 0, 0, 0xff];
+    let green = [0, 255, 0, 0xff];
+    let blue = [0, 0, 255, 0xff];
+    let row = [red, red, green, green, blue, blue].concat();
+
+    assert_eq!(image_data, [row.clone(), row].concat());
+  }
+
+  #[test]
+  fn histogram_counts_every_pixel() {
+    let canvas = Dimensions {
+      width: 40,
+      height: 30,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.5, -1.2, 1.2).unwrap();
+    let histogram = iteration_histogram(canvas, axes_ranges, MAX_ITERS).unwrap();
+    let iters = compute_mandel_iters(canvas, axes_ranges, MAX_ITERS).unwrap();
+
+    assert_eq!(histogram.len(), MAX_ITERS as usize + 1);
+    assert_eq!(histogram.iter().sum::<u32>(), canvas.width * canvas.height);
+
+    for (idx, &count) in histogram.iter().enumerate() {
+      assert_eq!(
+        count as usize,
+        iters.iter().filter(|&&iters| iters as usize == idx).count()
+      );
+    }
+  }
+
+  #[test]
+  fn stretched_image_repeats_nearest_pixels() {
+    let from = Dimensions {
+      width: 2,
+      height: 1,
+    };
+    let to = Dimensions {
+      width: 4,
+      height: 2,
+    };
+    let row = [[1, 2, 3, 4], [1, 2, 3, 4], [5, 6, 7, 8], [5, 6, 7, 8]].concat();
+
+    assert_eq!(
+      stretch_image(&[1, 2, 3, 4, 5, 6, 7, 8], &from, &to),
+      [row.clone(), row].concat()
+    );
+  }
+
+  #[test]
+  fn orbit_stops_after_escaping() {
+    assert_eq!(
+      mandel_orbit(1.0, 0.0, MAX_ITERS),
+      vec![0.0, 0.0, 1.0, 0.0, 2.0, 0.0, 5.0, 0.0]
+    );
+    assert_eq!(mandel_orbit(0.0, 0.0, 3), vec![0.0; 8]);
+  }
+
+  #[test]
+  fn suggested_iterations_grow_with_zoom() {
+    let full_view = gen_struct_axes_ranges(-2.0, 0.5, -1.2, 1.2).unwrap();
+    let deep_zoom = gen_struct_axes_ranges(-0.75, -0.75 + 1e-10, 0.1, 0.1 + 1e-10).unwrap();
+
+    assert_eq!(suggest_max_iters(full_view), MIN_SUGGESTED_ITERS);
+    assert_eq!(suggest_max_iters(deep_zoom), 2302);
+  }
+
+  #[test]
+  fn flipped_image_reverses_pixels_and_rows() {
+    let canvas = Dimensions {
+      width: 3,
+      height: 2,
+    };
+    let pixels: Vec<u8> = (0..6).flat_map(|pixel| vec![pixel; 4]).collect();
+    let flipped = |flip_x, flip_y| {
+      let mut image_data = pixels.clone();
+      flip_image(&mut image_data, &canvas, flip_x, flip_y);
+      image_data
+        .chunks_exact(4)
+        .map(|pixel_data| pixel_data[0])
+        .collect::<Vec<u8>>()
+    };
+
+    assert_eq!(flipped(false, false), vec![0, 1, 2, 3, 4, 5]);
+    assert_eq!(flipped(true, false), vec![2, 1, 0, 5, 4, 3]);
+    assert_eq!(flipped(false, true), vec![3, 4, 5, 0, 1, 2]);
+    assert_eq!(flipped(true, true), vec![5, 4, 3, 2, 1, 0]);
+  }
+
+  #[test]
+  fn quadrant_is_reflected_into_all_four_corners() {
+    for &(width, height) in [(4, 2), (5, 3)].iter() {
+      let canvas = Dimensions { width, height };
+      let axes_ranges = gen_struct_axes_ranges(0.0, 1.0, 0.0, 1.0).unwrap();
+      let to_coord = pixel_to_point(&canvas, &axes_ranges);
+      let values = map_quadrant_pixels(&canvas, &axes_ranges, |this_coord| {
+        (this_coord.x * 100.0).round() as i32 * 1000 + (this_coord.y * 100.0).round() as i32
+      });
+
+      for iy in 0..height {
+        for ix in 0..width {
+          let mirrored = values[((height - 1 - iy) * width + (width - 1 - ix)) as usize];
+          assert_eq!(values[(iy * width + ix) as usize], mirrored);
+
+          if ix < width.div_ceil(2) && iy < height.div_ceil(2) {
+            let this_coord = to_coord(ix, iy);
+            let expected =
+              (this_coord.x * 100.0).round() as i32 * 1000 + (this_coord.y * 100.0).round() as i32;
+            assert_eq!(values[(iy * width + ix) as usize], expected);
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn sine_palette_follows_each_channel_frequency() {
+    let colour_map = sine_colour_map(
+      4,
+      [0.0, std::f64::consts::PI, 2.0 * std::f64::consts::PI],
+      0.0,
+    );
+
+    assert_eq!(colour_map.len(), 4);
+    assert_eq!(colour_map[0], vec![128, 128, 128]);
+    assert_eq!(colour_map[1], vec![128, 218, 255]);
+    assert_eq!(colour_map[2], vec![128, 255, 128]);
+  }
+
+  #[test]
+  fn batch_escape_times_match_single_points() {
+    let points = [0.0, 0.0, 2.5, 0.0, 0.3, 0.5, -1.0];
+
+    assert_eq!(
+      escape_times_batch(&points, MAX_ITERS),
+      vec![MAX_ITERS, 1, escape_time_at(0.3, 0.5, MAX_ITERS) as u32]
+    );
+  }
+
+  #[test]
+  fn region_is_copied_into_place() {
+    let canvas = Dimensions {
+      width: 3,
+      height: 3,
+    };
+    let region = Region {
+      x: 1,
+      y: 1,
+      width: 2,
+      height: 1,
+    };
+    let mut image_data = vec![0u8; 36];
+
+    copy_region(&mut image_data, &canvas, &[1, 1, 1, 1, 2, 2, 2, 2], &region);
+
+    let mut expected = vec![0u8; 36];
+    expected[16..24].copy_from_slice(&[1, 1, 1, 1, 2, 2, 2, 2]);
+    assert_eq!(image_data, expected);
+  }
+
+  #[test]
+  fn period_3_check_does_not_change_output() {
+    let canvas = Dimensions {
+      width: 120,
+      height: 96,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.5, -1.0, 1.0).unwrap();
+    let params = EscapeParams::new(MAX_ITERS);
+    let checked = EscapeParams {
+      check_period_3: true,
+      ..params
+    };
+
+    for &(x, y, _) in PERIOD_3_DISKS.iter() {
+      assert!(is_in_period_3_component(&Point { x, y }));
+      assert!(!mandel_early_bailout(&Point { x, y }));
+    }
+
+    assert_eq!(
+      compute_fractal_buffer(&canvas, &axes_ranges, &params, &FractalType::Mandelbrot),
+      compute_fractal_buffer(&canvas, &axes_ranges, &checked, &FractalType::Mandelbrot)
+    );
+  }
+
+  #[test]
+  fn grayscale_level_scales_iterations_to_255() {
+    assert_eq!(grayscale_level(0, MAX_ITERS), 0);
+    assert_eq!(grayscale_level(50, MAX_ITERS), 128);
+    assert_eq!(grayscale_level(MAX_ITERS as usize, MAX_ITERS), 255);
+    assert_eq!(grayscale_level(MAX_ITERS as usize + 1, MAX_ITERS), 255);
+  }
+
+  #[test]
+  fn minibrot_search_finds_nearby_nuclei() {
+    // Nucleus of the period-3 minibrot on the real axis
+    let minibrot = find_minibrot_near(-1.75, 0.0, 0.01, 1000);
+    assert!((minibrot.x + 1.754_877_666_246_693).abs() < 1e-9);
+    assert!(minibrot.y.abs() < 1e-9);
+
+    // The period-3 bulb is preferred over the smaller components around it
+    let bulb = find_minibrot_near(-0.1, 0.7, 0.1, 1000);
+    assert!((bulb.x + 0.122_561_166_876_653_6).abs() < 1e-9);
+    assert!((bulb.y - 0.744_861_766_619_744_2).abs() < 1e-9);
+
+    // Nothing lies inside the set this far out, so the search centre is returned unchanged
+    let nothing = find_minibrot_near(2.0, 2.0, 0.1, 1000);
+    assert_eq!((nothing.x, nothing.y), (2.0, 2.0));
+  }
+
+  #[test]
+  fn dithering_offsets_average_to_zero_over_each_block() {
+    let thresholds: Vec<f64> = (0..16).map(|idx| bayer_threshold(idx, 4)).collect();
+    let mut sorted = thresholds.clone();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    assert!(thresholds.iter().all(|&t| t > -0.5 && t < 0.5));
+    assert!(sorted.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(thresholds.iter().sum::<f64>().abs() < 1e-12);
+  }
+
+  #[test]
+  fn dithering_integer_iterations_does_not_change_linear_colours() {
+    let canvas = Dimensions {
+      width: 12,
+      height: 8,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.5, -1.0, 1.0).unwrap();
+    let colour_map: Vec<Vec<u32>> = (0..=MAX_ITERS).map(|idx| vec![idx, 0, 0]).collect();
+    let render = |smooth: bool, dither: bool| {
+      render_fractal(
+        &canvas,
+        &axes_ranges,
+        EscapeParams::new(MAX_ITERS),
+        &colour_map,
+        FractalType::Mandelbrot,
+        &RenderOptions {
+          smooth,
+          dither,
+          ..RenderOptions::new(true)
+        },
+      )
+    };
+
+    assert_eq!(render(false, true), render(false, false));
+
+    // Dithered smooth colours only ever use the entries either side of the fractional iteration value
+    let smooth = render(true, false);
+    let dithered = render(true, true);
+
+    for (plain, dith) in smooth.chunks_exact(4).zip(dithered.chunks_exact(4)) {
+      assert!((plain[0] as i32 - dith[0] as i32).abs() <= 1);
+    }
+  }
+
+  #[test]
+  fn area_estimate_is_close_to_known_value() {
+    let area = estimate_mandelbrot_area(200, 500);
+
+    assert!((area - 1.5066).abs() < 0.05, "area was {}", area);
+    assert_eq!(estimate_mandelbrot_area(0, 500), 0.0);
+  }
+
+  #[test]
+  fn colour_map_alpha_is_used_when_present() {
+    let canvas = Dimensions {
+      width: 5,
+      height: 3,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.0, -1.0, 1.0).unwrap();
+    let colour_map = vec![vec![0, 0, 0], vec![10, 20, 30, 0], vec![40, 50, 60, 128]];
+    let render = |alpha: u8| {
+      render_fractal(
+        &canvas,
+        &axes_ranges,
+        EscapeParams::new(MAX_ITERS),
+        &colour_map,
+        FractalType::Mandelbrot,
+        &RenderOptions {
+          alpha,
+          ..RenderOptions::new(true)
+        },
+      )
+    };
+
+    // The centre of the canvas is inside the set and uses the last entry, and the top left corner escapes after one
+    // iteration
+    let opaque = render(0xff);
+    assert_eq!(opaque[28..32], [40, 50, 60, 128]);
+    assert_eq!(opaque[0..4], [10, 20, 30, 0]);
+
+    // The alpha argument scales the opacity of the colour map
+    assert_eq!(render(0x80)[28..32], [40, 50, 60, 64]);
+
+    // Entries without a 4th value are opaque
+    let mut pixel = [0u8; 4];
+    write_rgba(&mut pixel, &[1, 2, 3], false);
+    assert_eq!(pixel, [0xff, 3, 2, 1]);
+  }
+
+  #[test]
+  fn julia_grid_cells_match_individual_renders() {
+    let canvas = Dimensions {
+      width: 11,
+      height: 6,
+    };
+    let julia_axes = gen_struct_axes_ranges(-1.5, 1.5, -1.5, 1.5).unwrap();
+    let colour_map: Vec<Vec<u32>> = (0..=MAX_ITERS).map(|idx| vec![idx, 0, 0]).collect();
+    let c_start = Point { x: -0.8, y: 0.0 };
+    let c_step = Point { x: 0.2, y: 0.3 };
+    let grid = render_julia_grid(
+      &canvas,
+      &Dimensions {
+        width: 2,
+        height: 2,
+      },
+      &julia_axes,
+      MAX_ITERS,
+      &colour_map,
+      true,
+      |col, row| Point {
+        x: c_start.x + col as f64 * c_step.x,
+        y: c_start.y + row as f64 * c_step.y,
+      },
+    );
+
+    // The bottom right cell starts at pixel (5, 3) and is 6 x 3 pixels
+    let cell = render_fractal(
+      &Dimensions {
+        width: 6,
+        height: 3,
+      },
+      &julia_axes,
+      EscapeParams::new(MAX_ITERS),
+      &colour_map,
+      FractalType::Julia(Point { x: -0.6, y: 0.3 }),
+      &RenderOptions::new(true),
+    );
+
+    for iy in 0..3 {
+      let start = ((3 + iy) * 11 + 5) * 4;
+      assert_eq!(grid[start..start + 24], cell[iy * 24..iy * 24 + 24]);
+    }
+  }
+
+  #[test]
+  fn zero_iterations_draw_the_first_colour_everywhere() {
+    let canvas = Dimensions {
+      width: 4,
+      height: 3,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.5, -1.0, 1.0).unwrap();
+    let colour_map = vec![vec![10, 20, 30], vec![40, 50, 60]];
+    let render = |smooth: bool, mask: RenderMask| {
+      render_fractal(
+        &canvas,
+        &axes_ranges,
+        EscapeParams::new(0),
+        &colour_map,
+        FractalType::Mandelbrot,
+        &RenderOptions {
+          smooth,
+          wrap_palette: true,
+          color_scale: ColorScale::Logarithmic,
+          mask,
+          interior_colour: Some([0, 0, 128]),
+          ..RenderOptions::new(true)
+        },
+      )
+    };
+
+    for &smooth in [false, true].iter() {
+      assert_eq!(
+        render(smooth, RenderMask::All),
+        [10, 20, 30, 0xff].repeat(12)
+      );
+      assert_eq!(
+        render(smooth, RenderMask::InteriorOnly),
+        [10, 20, 30, 0].repeat(12)
+      );
+    }
+  }
+
+  #[test]
+  fn centre_and_zoom_give_square_pixels() {
+    let landscape = Dimensions {
+      width: 201,
+      height: 101,
+    };
+    let ranges = fit_ranges_to_aspect(Point { x: -0.5, y: 0.25 }, 1.5, landscape);
+
+    assert_eq!((ranges.x_range.min, ranges.x_range.max), (-2.0, 1.0));
+    assert_eq!((ranges.y_range.min, ranges.y_range.max), (-0.5, 1.0));
+
+    let portrait = Dimensions {
+      width: 101,
+      height: 201,
+    };
+    let ranges = fit_ranges_to_aspect(Point { x: 0.0, y: 0.0 }, 2.0, portrait);
+
+    assert_eq!((ranges.x_range.min, ranges.x_range.max), (-1.0, 1.0));
+    assert_eq!((ranges.y_range.min, ranges.y_range.max), (-2.0, 2.0));
+  }
+
+  #[test]
+  fn test_pattern_alternates_on_integer_coordinates() {
+    let colour_at = |x: f64, y: f64| test_pattern_colour(&Point { x, y });
+    let white = [0xff, 0xff, 0xff];
+    let black = [0, 0, 0];
+
+    assert_eq!(colour_at(0.5, 0.5), white);
+    assert_eq!(colour_at(-0.5, -0.5), white);
+    assert_eq!(colour_at(-0.5, 0.5), black);
+    assert_eq!(colour_at(1.0, 0.0), black);
+    assert_eq!(colour_at(2.5, -0.5), black);
+  }
+
+  #[test]
+  fn potential_falls_towards_the_set() {
+    assert_eq!(
+      mandel_potential(&Point { x: -1.0, y: 0.0 }, &MAX_ITERS),
+      0.0
+    );
+    assert_eq!(mandel_potential(&Point { x: 0.2, y: 0.0 }, &MAX_ITERS), 0.0);
+
+    // Far from the set, the potential approaches ln|c|
+    let far = mandel_potential(&Point { x: 1000.0, y: 0.0 }, &MAX_ITERS);
+    assert!((far - 1000f64.ln()).abs() < 1e-3);
+
+    let near = mandel_potential(&Point { x: 0.26, y: 0.0 }, &MAX_ITERS);
+    let nearer = mandel_potential(&Point { x: 0.255, y: 0.0 }, &MAX_ITERS);
+    assert!(0.0 < nearer && nearer < near && near < far);
+  }
+
+  #[test]
+  fn panning_keeps_the_dragged_point_under_the_pointer() {
+    let canvas = Dimensions {
+      width: 11,
+      height: 21,
+    };
+    let current = gen_struct_axes_ranges(0.0, 10.0, -10.0, 10.0).unwrap();
+    let grabbed = fractional_pixel_to_point(&canvas, &current)(3.0, 4.0);
+    let panned = pan_ranges(current, canvas, 5.0, -2.0);
+
+    assert_eq!((panned.x_range.min, panned.x_range.max), (-5.0, 5.0));
+    assert_eq!((panned.y_range.min, panned.y_range.max), (-8.0, 12.0));
+
+    let dropped = fractional_pixel_to_point(&canvas, &panned)(8.0, 2.0);
+    assert_eq!((dropped.x, dropped.y), (grabbed.x, grabbed.y));
+  }
+
+  #[test]
+  fn u16_iterations_are_clamped() {
+    let canvas = Dimensions {
+      width: 8,
+      height: 6,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.5, -1.0, 1.0).unwrap();
+    let narrow: Vec<u32> = compute_mandel_iters_u16(canvas, axes_ranges, MAX_ITERS)
+      .unwrap()
+      .into_iter()
+      .map(u32::from)
+      .collect();
+    assert_eq!(
+      narrow,
+      compute_mandel_iters(canvas, axes_ranges, MAX_ITERS).unwrap()
+    );
+
+    // Every point in this view lies in the period-2 bulb
+    let bulb = gen_struct_axes_ranges(-1.05, -0.95, -0.05, 0.05).unwrap();
+    assert!(compute_mandel_iters_u16(canvas, bulb, 70_000)
+      .unwrap()
+      .iter()
+      .all(|&iters| iters == u16::MAX));
+  }
+
+  #[test]
+  fn bailout_regions_are_tinted() {
+    let canvas = Dimensions {
+      width: 3,
+      height: 2,
+    };
+    let grey = [0x80, 0x80, 0x80, 0xff];
+
+    // Each row has one pixel in the period-2 bulb at x = -1, and two in the main cardioid at x = -0.5 and x = 0
+    let axes_ranges = gen_struct_axes_ranges(-1.0, 0.0, -0.01, 0.01).unwrap();
+    let mut image_data = grey.repeat(6);
+    tint_bailout_regions(&mut image_data, &canvas, &axes_ranges, false, true);
+    assert_eq!(
+      image_data,
+      [0x40, 0xbf, 0x40, 0xff, 0xbf, 0x40, 0x40, 0xff, 0xbf, 0x40, 0x40, 0xff].repeat(2)
+    );
+
+    // Points outside every region are left alone
+    let outside = gen_struct_axes_ranges(0.5, 0.6, -0.01, 0.01).unwrap();
+    let mut image_data = grey.repeat(6);
+    tint_bailout_regions(&mut image_data, &canvas, &outside, true, true);
+    assert_eq!(image_data, grey.repeat(6));
+
+    // The period-3 components are only tinted when they are checked
+    let (x, y, _) = PERIOD_3_DISKS[0];
+    let bulb = gen_struct_axes_ranges(x - 0.01, x + 0.01, y - 0.01, y + 0.01).unwrap();
+    let mut image_data = [0xff, 0x80, 0x80, 0x80].repeat(6);
+    tint_bailout_regions(&mut image_data, &canvas, &bulb, false, false);
+    assert_eq!(image_data, [0xff, 0x80, 0x80, 0x80].repeat(6));
+    tint_bailout_regions(&mut image_data, &canvas, &bulb, true, false);
+    assert_eq!(image_data, [0xff, 0xbf, 0x40, 0x40].repeat(6));
+  }
+
+  #[test]
+  fn complex_numbers_deserialize_from_re_and_im() {
+    let c: Complex = serde_json::from_str(r#"{"re": -0.8, "im": 0.156}"#).unwrap();
+
+    assert_eq!(c, Complex::new(-0.8, 0.156));
+    assert!(serde_json::from_str::<Complex>(r#"{"x": -0.8, "y": 0.156}"#).is_err());
+  }
+
+  #[test]
+  fn png_render_rejects_empty_canvas() {
+    let canvas = Dimensions {
+      width: 0,
+      height: 10,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.5, -1.2, 1.2).unwrap();
+
+    // The dimensions are checked before the colour map is read
+    assert!(matches!(
+      render_mandel_png(canvas, axes_ranges, MAX_ITERS, JsValue::NULL, true),
+      Err(FractalError::InvalidDimensions(_))
+    ));
+  }
+
+  #[test]
+  fn tricorn_matches_mandelbrot_on_real_axis() {
+    let params = EscapeParams::new(MAX_ITERS);
+
+    // On the real axis, z is always real, so conjugating it has no effect
+    for &x in [-2.1, -1.9, -1.5, -1.0, -0.5, 0.0, 0.2, 0.26, 0.5].iter() {
+      let loc = Point { x, y: 0.0 };
+      assert_eq!(
+        tricorn_iter(&loc, &params),
+        escape_time_mj(&loc, Point { x: 0.0, y: 0.0 }, &params)
+      );
+    }
+
+    // Away from the real axis, the Tricorn is still symmetric about it
+    for &(x, y) in [(-0.5, 0.6), (0.3, 0.4), (-1.2, 0.3)].iter() {
+      assert_eq!(
+        tricorn_iter(&Point { x, y }, &params),
+        tricorn_iter(&Point { x, y: -y }, &params)
+      );
+    }
+  }
+
+  #[test]
+  fn newton_converges_to_nearest_root() {
+    // From z = 2, the iterates are 1.4167, 1.1105, 1.0106, 1.0001, then within NEWTON_EPSILON of 1
+    assert_eq!(newton_iter(&Point { x: 2.0, y: 0.0 }, &MAX_ITERS), (0, 5));
+
+    // Points on the rays through the other two roots converge to those roots
+    assert_eq!(newton_iter(&Point { x: -1.0, y: 1.732 }, &MAX_ITERS).0, 1);
+    assert_eq!(newton_iter(&Point { x: -1.0, y: -1.732 }, &MAX_ITERS).0, 2);
+
+    // The derivative is zero at the origin, so it never converges
+    assert_eq!(
+      newton_iter(&Point { x: 0.0, y: 0.0 }, &MAX_ITERS),
+      (NEWTON_ROOTS.len(), MAX_ITERS as usize)
+    );
+  }
+
+  #[test]
+  fn phoenix_has_known_values() {
+    let params = EscapeParams::new(MAX_ITERS);
+    let c = Point { x: 0.5, y: 0.0 };
+
+    // With p = 0, the Phoenix is the Mandelbrot Set: 0, 0.5, 0.75, 1.0625, 1.6289, 3.1533 escapes after 5 iterations
+    assert_eq!(phoenix_iter(&c, &params, &Point { x: 0.0, y: 0.0 }), 5);
+
+    // p = -0.5 pulls z back towards its previous value: 0.5, 0.75, 0.8125, 0.7852, ... so the orbit never escapes
+    assert_eq!(
+      phoenix_iter(&c, &params, &Point { x: -0.5, y: 0.0 }),
+      MAX_ITERS as usize
+    );
+  }
+
+  #[test]
+  fn nova_has_known_values() {
+    let relaxation = Complex::new(1.0, 0.0);
+    let nova = |x: f64, y: f64| nova_iter(&Point { x, y }, &relaxation, &MAX_ITERS);
+
+    // With c = 0, the starting value z = 1 is already a root of z^3 - 1
+    assert_eq!(nova(0.0, 0.0), (0, 0));
+
+    // With c = 1, z settles on the real root of z^3 - 3z^2 - 1 (about 3.1038), and the nearest root of z^3 - 1 is 1
+    let (root_idx, iters) = nova(1.0, 0.0);
+    assert_eq!(root_idx, 0);
+    assert!(iters > 0 && iters < MAX_ITERS as usize);
+
+    // With c = -1, the first step lands on the origin, where the derivative is zero
+    assert_eq!(nova(-1.0, 0.0), (NEWTON_ROOTS.len(), MAX_ITERS as usize));
+  }
+
+  #[test]
+  fn buddhabrot_hits_match_escaped_orbit_lengths() {
+    let canvas = Dimensions {
+      width: 41,
+      height: 41,
+    };
+    // Every point on an orbit that has not yet escaped satisfies |z| <= 2, so this view catches every hit
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 2.0, -2.0, 2.0).unwrap();
+    let (samples, max_iters) = (2000, 50);
+    let density = buddhabrot_density(&canvas, &axes_ranges, samples, &max_iters);
+
+    // Replay the same samples and add up the number of orbit points recorded for each value of c that escapes
+    let params = EscapeParams::new(max_iters);
+    let mut rng = XorShift64::new(BUDDHABROT_SEED);
+    let mut expected = 0u64;
+
+    for _ in 0..samples {
+      let loc = Point {
+        x: rng.next_in_range(-BUDDHABROT_SAMPLE_RANGE, BUDDHABROT_SAMPLE_RANGE),
+        y: rng.next_in_range(-BUDDHABROT_SAMPLE_RANGE, BUDDHABROT_SAMPLE_RANGE),
+      };
+
+      if mandel_early_bailout(&loc) {
+        continue;
+      }
+
+      // The orbit holds every value of z before the one that escaped
+      let (iters, z) = escape_time_with_z(&loc, Point { x: 0.0, y: 0.0 }, &params, mj_step);
+
+      if z.abs_sq() > BAILOUT {
+        expected += iters as u64 - 1;
+      }
+    }
+
+    assert!(expected > 0);
+    assert_eq!(
+      density.iter().map(|&hits| hits as u64).sum::<u64>(),
+      expected
+    );
+  }
+
+  #[test]
+  fn single_pixel_canvas_maps_to_axes_minimum() {
+    let canvas = Dimensions {
+      width: 1,
+      height: 1,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.5, -1.2, 1.2).unwrap();
+    let point = pixel_to_point(&canvas, &axes_ranges)(0, 0);
+
+    assert_eq!((point.x, point.y), (-2.0, -1.2));
+    assert_eq!(
+      point_to_pixel(&canvas, &axes_ranges)(-2.0, -1.2),
+      (0.0, 0.0)
+    );
+  }
+
+  #[test]
+  fn iteration_buffers_reject_empty_canvas() {
+    let canvas = Dimensions {
+      width: 0,
+      height: 10,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.5, -1.2, 1.2).unwrap();
+    let is_invalid =
+      |result: Result<(), FractalError>| matches!(result, Err(FractalError::InvalidDimensions(_)));
+
+    assert!(is_invalid(
+      compute_mandel_iters(canvas, axes_ranges, MAX_ITERS).map(|_| ())
+    ));
+    assert!(is_invalid(
+      compute_mandel_iters_u16(canvas, axes_ranges, MAX_ITERS).map(|_| ())
+    ));
+    assert!(is_invalid(
+      compute_mandel_smooth_f32(canvas, axes_ranges, MAX_ITERS).map(|_| ())
+    ));
+    assert!(is_invalid(
+      iteration_histogram(canvas, axes_ranges, MAX_ITERS).map(|_| ())
+    ));
+  }
+
+  // Reference implementation for Complex::powi: raise x + iy to the integer power n by repeated multiplication
+  fn complex_pow(x: f64, y: f64, n: u32) -> (f64, f64) {
+    if n == 0 {
+      return (1.0, 0.0);
+    }
+
+    let (mut re, mut im) = (x, y);
+
+    for _ in 1..n {
+      let new_re = re * x - im * y;
+      im = re * y + im * x;
+      re = new_re;
+    }
+
+    (re, im)
+  }
+
+  #[test]
+  fn fast_complex_powers_match_repeated_multiplication() {
+    let z = Complex::new(0.7, -0.45);
+
+    for power in 0..=12 {
+      let (re, im) = complex_pow(z.re, z.im, power);
+      let fast = z.powi(power);
+
+      assert!((fast.re - re).abs() < 1e-12 && (fast.im - im).abs() < 1e-12);
+    }
+
+    let (re, im) = complex_pow(z.re, z.im, 3);
+    assert!((z.sqr().mul(z).re - re).abs() < 1e-12 && (z.sqr().mul(z).im - im).abs() < 1e-12);
+
+    let (re, im) = complex_pow(z.re, z.im, 4);
+    assert!((z.sqr().sqr().re - re).abs() < 1e-12 && (z.sqr().sqr().im - im).abs() < 1e-12);
+  }
+
+  // Benchmarks comparing naive and optimized complex powers: run with cargo test --release -- --ignored
+  // Each benchmark renders the same 200 x 200 power 8 Multibrot and returns the time taken and the total iteration count
+  fn time_multibrot<F: Fn(Complex) -> Complex>(pow: F) -> (std::time::Duration, u32) {
+    let start = std::time::Instant::now();
+    let mut total = 0;
+
+    for py in 0..200 {
+      for px in 0..200 {
+        let c = Complex::new(-1.2 + px as f64 * 0.012, -1.2 + py as f64 * 0.012);
+        let mut z = Complex::new(0.0, 0.0);
+        let mut iters = 0;
+
+        while iters < MAX_ITERS && z.abs_sq() <= BAILOUT {
+          z = pow(z).add(c);
+          iters += 1;
+        }
+
+        total += iters;
+      }
+    }
+
+    (start.elapsed(), total)
+  }
+
+  fn naive_power_8(z: Complex) -> Complex {
+    let (re, im) = complex_pow(z.re, z.im, 8);
+    Complex::new(re, im)
+  }
+
+  #[test]
+  #[ignore]
+  fn bench_multibrot_power_8_naive() {
+    let (elapsed, total) = time_multibrot(naive_power_8);
+
+    assert!(total > 0);
+    assert!(elapsed.as_secs() < 10, "naive power 8 took {:?}", elapsed);
+  }
+
+  #[test]
+  #[ignore]
+  fn bench_multibrot_power_8_squaring() {
+    let (naive_elapsed, naive_total) = time_multibrot(naive_power_8);
+    let (elapsed, total) = time_multibrot(|z| z.powi(8));
+
+    assert_eq!(total, naive_total);
+    assert!(
+      elapsed <= naive_elapsed,
+      "squaring power 8 took {:?}, but the naive loop took {:?}",
+      elapsed,
+      naive_elapsed
+    );
+  }
+
+  #[cfg(feature = "simd")]
+  #[test]
+  fn simd_matches_scalar_escape_time() {
+    let canvas = Dimensions {
+      width: 37,
+      height: 23,
+    };
+    let axes_ranges = gen_struct_axes_ranges(-2.0, 0.5, -1.2, 1.2).unwrap();
+    let mouse_loc = Point { x: -0.8, y: 0.156 };
+    let params = EscapeParams::new(MAX_ITERS);
+
+    for f_type in [FractalType::Mandelbrot, FractalType::Julia(mouse_loc)].iter() {
+      let scalar = map_pixels(&canvas, &axes_ranges, |this_coord| {
+        fractal_iter(f_type, this_coord, &params)
+      });
+      let simd = compute_buffer_simd(&canvas, &axes_ranges, &params, f_type);
+
+      assert_eq!(scalar, simd);
+    }
+  }
 }
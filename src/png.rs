@@ -0,0 +1,227 @@
+// *********************************************************************************************************************
+// Minimal PNG encoder for 8-bit RGBA image data
+//
+// The image data is stored in uncompressed deflate blocks, so the files are larger than those produced by a general
+// purpose encoder, but they are valid PNG files that any browser or image viewer can open
+// *********************************************************************************************************************
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+// The largest amount of data that can be held in a single uncompressed deflate block
+const MAX_STORED_BLOCK: usize = 0xffff;
+
+/***********************************************************************************************************************
+ * Encode RGBA pixel data as a PNG file
+ * The pixel data must hold width * height * 4 bytes in row-major order
+ */
+pub fn encode_rgba(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+  let mut png = PNG_SIGNATURE.to_vec();
+
+  // Image header: dimensions, 8 bits per channel, colour type 6 (RGBA), default compression, filter and interlace
+  let mut ihdr = Vec::with_capacity(13);
+  ihdr.extend_from_slice(&width.to_be_bytes());
+  ihdr.extend_from_slice(&height.to_be_bytes());
+  ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+  write_chunk(&mut png, b"IHDR", &ihdr);
+
+  // Each row of pixels is preceded by a filter type byte of 0 (no filtering)
+  let row_len = width as usize * 4;
+  let mut raw = Vec::with_capacity((row_len + 1) * height as usize);
+
+  for row in rgba.chunks_exact(row_len.max(1)).take(height as usize) {
+    raw.push(0);
+    raw.extend_from_slice(row);
+  }
+
+  write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+  write_chunk(&mut png, b"IEND", &[]);
+
+  png
+}
+
+/***********************************************************************************************************************
+ * Write a PNG chunk: length, type, data, then a CRC of the type and data
+ */
+fn write_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+  png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+  let crc_start = png.len();
+  png.extend_from_slice(chunk_type);
+  png.extend_from_slice(data);
+
+  let crc = crc32(&png[crc_start..]);
+  png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/***********************************************************************************************************************
+ * Wrap data in a zlib stream made of uncompressed deflate blocks
+ */
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+  let block_count = data.len().div_ceil(MAX_STORED_BLOCK).max(1);
+  let mut zlib = Vec::with_capacity(data.len() + block_count * 5 + 6);
+
+  // Deflate compression with a 32K window and no preset dictionary
+  zlib.extend_from_slice(&[0x78, 0x01]);
+
+  let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+
+  if blocks.peek().is_none() {
+    zlib.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]);
+  }
+
+  while let Some(block) = blocks.next() {
+    let is_final = blocks.peek().is_none();
+    let len = block.len() as u16;
+
+    zlib.push(is_final as u8);
+    zlib.extend_from_slice(&len.to_le_bytes());
+    zlib.extend_from_slice(&(!len).to_le_bytes());
+    zlib.extend_from_slice(block);
+  }
+
+  zlib.extend_from_slice(&adler32(data).to_be_bytes());
+  zlib
+}
+
+/***********************************************************************************************************************
+ * Checksums
+ */
+fn crc32(data: &[u8]) -> u32 {
+  let mut crc = 0xffff_ffffu32;
+
+  for &byte in data {
+    crc ^= byte as u32;
+
+    for _ in 0..8 {
+      crc = if crc & 1 == 1 {
+        (crc >> 1) ^ 0xedb8_8320
+      } else {
+        crc >> 1
+      };
+    }
+  }
+
+  !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+  const MOD_ADLER: u32 = 65521;
+  let (mut a, mut b) = (1u32, 0u32);
+
+  for &byte in data {
+    a = (a + byte as u32) % MOD_ADLER;
+    b = (b + a) % MOD_ADLER;
+  }
+
+  (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Decode a zlib stream made of uncompressed deflate blocks, checking its header, block lengths and checksum
+  fn inflate_stored(zlib: &[u8]) -> Vec<u8> {
+    assert_eq!(zlib[0] & 0x0f, 8);
+    assert_eq!(u16::from_be_bytes([zlib[0], zlib[1]]) % 31, 0);
+
+    let mut data = Vec::new();
+    let mut pos = 2;
+
+    loop {
+      let header = zlib[pos];
+      let len = u16::from_le_bytes([zlib[pos + 1], zlib[pos + 2]]);
+      let nlen = u16::from_le_bytes([zlib[pos + 3], zlib[pos + 4]]);
+
+      // Block type 0 means stored
+      assert_eq!(header >> 1, 0);
+      assert_eq!(len, !nlen);
+
+      pos += 5;
+      data.extend_from_slice(&zlib[pos..pos + len as usize]);
+      pos += len as usize;
+
+      if header & 1 == 1 {
+        break;
+      }
+    }
+
+    assert_eq!(zlib[pos..].to_vec(), adler32(&data).to_be_bytes().to_vec());
+    data
+  }
+
+  // Decode a PNG file produced by encode_rgba, returning its dimensions and RGBA pixel data
+  fn decode_rgba(png: &[u8]) -> (u32, u32, Vec<u8>) {
+    assert_eq!(png[..8], PNG_SIGNATURE);
+
+    let (mut width, mut height) = (0, 0);
+    let mut idat = Vec::new();
+    let mut pos = 8;
+
+    loop {
+      let len = u32::from_be_bytes([png[pos], png[pos + 1], png[pos + 2], png[pos + 3]]) as usize;
+      let chunk = &png[pos + 4..pos + 8 + len];
+      let crc = &png[pos + 8 + len..pos + 12 + len];
+
+      assert_eq!(crc, crc32(chunk).to_be_bytes());
+
+      let (chunk_type, data) = chunk.split_at(4);
+      pos += 12 + len;
+
+      match chunk_type {
+        b"IHDR" => {
+          width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+          height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+          assert_eq!(data[8..], [8, 6, 0, 0, 0]);
+        }
+        b"IDAT" => idat.extend_from_slice(data),
+        b"IEND" => break,
+        _ => panic!("unexpected chunk type {:?}", chunk_type),
+      }
+    }
+
+    assert_eq!(pos, png.len());
+
+    let raw = inflate_stored(&idat);
+    let row_len = width as usize * 4;
+    let mut rgba = Vec::with_capacity(row_len * height as usize);
+
+    assert_eq!(raw.len(), (row_len + 1) * height as usize);
+
+    for row in raw.chunks_exact(row_len + 1) {
+      assert_eq!(row[0], 0);
+      rgba.extend_from_slice(&row[1..]);
+    }
+
+    (width, height, rgba)
+  }
+
+  #[test]
+  fn checksums_have_known_values() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    assert_eq!(crc32(b"IEND"), 0xae42_6082);
+
+    assert_eq!(adler32(b""), 1);
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+  }
+
+  #[test]
+  fn encoded_image_decodes_to_original_pixels() {
+    // 130 x 130 RGBA pixels need more than one stored deflate block
+    let (width, height) = (130, 130);
+    let rgba: Vec<u8> = (0..width * height * 4)
+      .map(|idx| (idx * 7 % 251) as u8)
+      .collect();
+
+    assert_eq!(
+      decode_rgba(&encode_rgba(width, height, &rgba)),
+      (width, height, rgba)
+    );
+  }
+
+  #[test]
+  fn empty_data_gives_valid_zlib_stream() {
+    assert!(inflate_stored(&zlib_stored(&[])).is_empty());
+  }
+}
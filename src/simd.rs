@@ -0,0 +1,149 @@
+// *********************************************************************************************************************
+// Escape time calculation for several pixels at once
+//
+// Each lane holds one pixel.  When WebAssembly SIMD is enabled (see .cargo/config.toml), pairs of lanes are calculated
+// with f64x2 vector instructions, and lanes that have escaped are masked out.  On every other target, the portable
+// version loops over the lanes without branches, leaving the compiler to vectorise it where it can
+// *********************************************************************************************************************
+
+use crate::EscapeParams;
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+use core::arch::wasm32::{
+  f64x2, f64x2_add, f64x2_le, f64x2_mul, f64x2_splat, f64x2_sub, i64x2, i64x2_extract_lane,
+  i64x2_splat, i64x2_sub, v128, v128_and, v128_any_true, v128_bitselect,
+};
+
+pub const LANES: usize = 4;
+
+/***********************************************************************************************************************
+ * Calculate the escape time of LANES points using z = z^2 + c
+ * Lanes marked as skipped are not iterated and are given a value of max_iters.  For all other lanes, the iteration
+ * counts are identical to those of the scalar escape_time_mj
+ */
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+pub fn escape_time_simd(
+  c_re: [f64; LANES],  // Real part of c for each lane
+  c_im: [f64; LANES],  // Imaginary part of c for each lane
+  z_re: [f64; LANES],  // Real part of the starting value of z for each lane
+  z_im: [f64; LANES],  // Imaginary part of the starting value of z for each lane
+  skip: [bool; LANES], // Lanes already known to be inside the set
+  params: &EscapeParams,
+) -> [u32; LANES] {
+  let mut iter_counts = [0u32; LANES];
+
+  for pair in (0..LANES).step_by(2) {
+    let lanes = |values: &[f64; LANES]| f64x2(values[pair], values[pair + 1]);
+    let counts = escape_time_f64x2(
+      lanes(&c_re),
+      lanes(&c_im),
+      lanes(&z_re),
+      lanes(&z_im),
+      [skip[pair], skip[pair + 1]],
+      params,
+    );
+
+    iter_counts[pair..pair + 2].copy_from_slice(&counts);
+  }
+
+  iter_counts
+}
+
+/***********************************************************************************************************************
+ * Calculate the escape time of two points held in the lanes of f64x2 vectors
+ * Each lane of the active mask is all ones until that lane escapes.  The mask selects which lanes of z are updated, and
+ * subtracting it from the iteration counts adds one to the count of every active lane
+ */
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+fn escape_time_f64x2(
+  c_re: v128,      // Real part of c for each lane
+  c_im: v128,      // Imaginary part of c for each lane
+  z_re: v128,      // Real part of the starting value of z for each lane
+  z_im: v128,      // Imaginary part of the starting value of z for each lane
+  skip: [bool; 2], // Lanes already known to be inside the set
+  params: &EscapeParams,
+) -> [u32; 2] {
+  let (mut z_re, mut z_im) = (z_re, z_im);
+  let (bailout, two) = (f64x2_splat(params.bailout), f64x2_splat(2.0));
+  let mut active = i64x2(-(!skip[0] as i64), -(!skip[1] as i64));
+  let mut iter_counts = i64x2_splat(0);
+
+  for _ in 0..params.max_iters {
+    let (re_sq, im_sq) = (f64x2_mul(z_re, z_re), f64x2_mul(z_im, z_im));
+    active = v128_and(active, f64x2_le(f64x2_add(re_sq, im_sq), bailout));
+
+    if !v128_any_true(active) {
+      break;
+    }
+
+    let next_re = f64x2_add(f64x2_sub(re_sq, im_sq), c_re);
+    let next_im = f64x2_add(f64x2_mul(f64x2_mul(two, z_re), z_im), c_im);
+
+    z_re = v128_bitselect(next_re, z_re, active);
+    z_im = v128_bitselect(next_im, z_im, active);
+    iter_counts = i64x2_sub(iter_counts, active);
+  }
+
+  let lane_count = |lane: usize, count: i64| {
+    if skip[lane] {
+      params.max_iters
+    } else {
+      count as u32
+    }
+  };
+
+  [
+    lane_count(0, i64x2_extract_lane::<0>(iter_counts)),
+    lane_count(1, i64x2_extract_lane::<1>(iter_counts)),
+  ]
+}
+
+/***********************************************************************************************************************
+ * Portable version of escape_time_simd for targets without WebAssembly SIMD
+ */
+#[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+pub fn escape_time_simd(
+  c_re: [f64; LANES],  // Real part of c for each lane
+  c_im: [f64; LANES],  // Imaginary part of c for each lane
+  z_re: [f64; LANES],  // Real part of the starting value of z for each lane
+  z_im: [f64; LANES],  // Imaginary part of the starting value of z for each lane
+  skip: [bool; LANES], // Lanes already known to be inside the set
+  params: &EscapeParams,
+) -> [u32; LANES] {
+  let (mut z_re, mut z_im) = (z_re, z_im);
+  let mut iter_counts = [0u32; LANES];
+  let mut active = [false; LANES];
+
+  for lane in 0..LANES {
+    active[lane] = !skip[lane];
+
+    if skip[lane] {
+      iter_counts[lane] = params.max_iters;
+    }
+  }
+
+  for _ in 0..params.max_iters {
+    let mut any_active = false;
+
+    for lane in 0..LANES {
+      let (re, im) = (z_re[lane], z_im[lane]);
+
+      // A lane stays active until its value of z escapes, after which z is frozen so that it never overflows
+      active[lane] &= re * re + im * im <= params.bailout;
+      any_active |= active[lane];
+
+      let next_re = re * re - im * im + c_re[lane];
+      let next_im = 2.0 * re * im + c_im[lane];
+
+      z_re[lane] = if active[lane] { next_re } else { re };
+      z_im[lane] = if active[lane] { next_im } else { im };
+      iter_counts[lane] += active[lane] as u32;
+    }
+
+    if !any_active {
+      break;
+    }
+  }
+
+  iter_counts
+}